@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Existing tokens get the migration time: they will expire one TTL from now.
+ALTER TABLE subscription_tokens ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
@@ -0,0 +1,8 @@
+-- Add migration script here
+-- Idempotency keys are now scoped to the authenticated publisher. Stored
+-- responses are disposable (see the cleanup worker), so we drop them rather
+-- than guess an owner.
+DELETE FROM idempotency;
+ALTER TABLE idempotency ADD COLUMN user_id UUID NOT NULL REFERENCES users (user_id);
+ALTER TABLE idempotency DROP CONSTRAINT idempotency_pkey;
+ALTER TABLE idempotency ADD PRIMARY KEY (user_id, idempotency_key);
@@ -0,0 +1,7 @@
+-- Add migration script here
+CREATE TABLE sessions (
+    session_key TEXT NOT NULL,
+    state JSONB NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY(session_key)
+);
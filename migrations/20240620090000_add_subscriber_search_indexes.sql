@@ -0,0 +1,8 @@
+-- Add migration script here
+-- Keyset pagination walks (subscribed_at, id), optionally within one status.
+CREATE INDEX subscriptions_subscribed_at_id_idx ON subscriptions (subscribed_at, id);
+CREATE INDEX subscriptions_status_subscribed_at_id_idx ON subscriptions (status, subscribed_at, id);
+-- Case-insensitive prefix search: `text_pattern_ops` lets `LIKE 'abc%'` use the index
+-- regardless of the database collation.
+CREATE INDEX subscriptions_lower_email_idx ON subscriptions (lower(email) text_pattern_ops);
+CREATE INDEX subscriptions_lower_name_idx ON subscriptions (lower(name) text_pattern_ops);
@@ -0,0 +1,10 @@
+-- Add migration script here
+-- Where a subscriber came from: 'signup' through the form, 'import' from a CSV upload.
+BEGIN;
+    ALTER TABLE subscriptions ADD COLUMN source TEXT NULL;
+    -- Every historical entry came through the sign-up form
+    UPDATE subscriptions
+        SET source = 'signup'
+        WHERE source IS NULL;
+    ALTER TABLE subscriptions ALTER COLUMN source SET NOT NULL;
+COMMIT;
@@ -0,0 +1,8 @@
+mod middleware;
+mod password;
+
+pub use middleware::{reject_anonymous_users, UserId};
+pub use password::{
+    change_password, compute_password_hash, create_user, validate_credentials, AuthError,
+    Credentials, MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH,
+};
@@ -0,0 +1,38 @@
+//! Creates an admin account, e.g. the first one of a new deployment:
+//!
+//! ```sh
+//! echo "$ADMIN_PASSWORD" | APP_ENVIRONMENT=production create_admin <username>
+//! ```
+//!
+//! The password is read from the first line of stdin, so it never shows up
+//! in the process list or in the shell history.
+use anyhow::Context;
+use secrecy::Secret;
+use zero2prod::authentication::{create_user, MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+use zero2prod::configuration::get_configuration;
+use zero2prod::startup::get_connection_pool;
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    let username = std::env::args()
+        .nth(1)
+        .context("Usage: create_admin <username> (password on stdin)")?;
+
+    let mut password = String::new();
+    std::io::stdin()
+        .read_line(&mut password)
+        .context("Failed to read the password from stdin")?;
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    let password_length = password.chars().count();
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password_length) {
+        anyhow::bail!(
+            "The password must be between {MIN_PASSWORD_LENGTH} and {MAX_PASSWORD_LENGTH} characters long."
+        );
+    }
+
+    let configuration = get_configuration().context("Failed to read configuration.")?;
+    let pool = get_connection_pool(&configuration.database);
+    let user_id = create_user(&username, Secret::new(password), &pool).await?;
+    println!("Created admin '{}' with id {}", username, user_id);
+    Ok(())
+}
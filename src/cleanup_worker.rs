@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::configuration::{CleanupSettings, Settings};
+use crate::startup::get_connection_pool;
+
+/// How many rows a cleanup run removed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CleanupOutcome {
+    pub idempotency_keys: u64,
+    pub expired_subscribers: u64,
+    pub expired_sessions: u64,
+}
+
+pub async fn run_cleanup_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
+    let connection_pool = get_connection_pool(&configuration.database);
+    cleanup_loop(connection_pool, configuration.cleanup).await
+}
+
+async fn cleanup_loop(pool: PgPool, settings: CleanupSettings) -> Result<(), anyhow::Error> {
+    loop {
+        // Failures are logged by `try_cleanup`: we will try again on the next tick.
+        let _ = try_cleanup(&pool, &settings).await;
+        tokio::time::sleep(settings.interval()).await;
+    }
+}
+
+/// Deletes idempotency records past their TTL, pending subscribers who never
+/// confirmed within the lifetime of their confirmation token and expired sessions.
+#[tracing::instrument(skip_all, err)]
+pub async fn try_cleanup(
+    pool: &PgPool,
+    settings: &CleanupSettings,
+) -> Result<CleanupOutcome, anyhow::Error> {
+    let idempotency_keys =
+        delete_expired_idempotency_keys(pool, settings.idempotency_ttl()).await?;
+    let expired_subscribers =
+        delete_expired_pending_subscribers(pool, settings.subscription_token_ttl()).await?;
+    let expired_sessions = delete_expired_sessions(pool).await?;
+    tracing::info!(
+        idempotency_keys,
+        expired_subscribers,
+        expired_sessions,
+        "Removed expired idempotency keys, unconfirmed subscribers and sessions",
+    );
+
+    Ok(CleanupOutcome {
+        idempotency_keys,
+        expired_subscribers,
+        expired_sessions,
+    })
+}
+
+#[tracing::instrument(skip(pool))]
+async fn delete_expired_sessions(pool: &PgPool) -> Result<u64, anyhow::Error> {
+    let deleted = sqlx::query!("DELETE FROM sessions WHERE expires_at < now()")
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted)
+}
+
+#[tracing::instrument(skip(pool))]
+async fn delete_expired_idempotency_keys(
+    pool: &PgPool,
+    ttl: Duration,
+) -> Result<u64, anyhow::Error> {
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM idempotency
+        WHERE created_at < now() - make_interval(secs => $1)
+        "#,
+        ttl.as_secs_f64()
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(deleted)
+}
+
+#[tracing::instrument(skip(pool))]
+async fn delete_expired_pending_subscribers(
+    pool: &PgPool,
+    ttl: Duration,
+) -> Result<u64, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    // Rows locked by an in-flight sign-up are left for the next run.
+    let expired: Vec<Uuid> = sqlx::query!(
+        r#"
+        SELECT id
+        FROM subscriptions s
+        WHERE
+            status = 'pending_confirmation' AND
+            NOT EXISTS (
+                SELECT 1
+                FROM subscription_tokens t
+                WHERE
+                    t.subscriber_id = s.id AND
+                    t.created_at >= now() - make_interval(secs => $1)
+            )
+        FOR UPDATE SKIP LOCKED
+        "#,
+        ttl.as_secs_f64()
+    )
+    .fetch_all(&mut *transaction)
+    .await?
+    .into_iter()
+    .map(|r| r.id)
+    .collect();
+
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    // Someone who had been confirmed before signing up again may still be
+    // referenced by the delivery tables.
+    sqlx::query!(
+        "DELETE FROM subscription_tokens WHERE subscriber_id = ANY($1)",
+        &expired
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM issue_delivery_queue WHERE subscriber_id = ANY($1)",
+        &expired
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM issue_delivery_dead_letters WHERE subscriber_id = ANY($1)",
+        &expired
+    )
+    .execute(&mut *transaction)
+    .await?;
+    let deleted = sqlx::query!("DELETE FROM subscriptions WHERE id = ANY($1)", &expired)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    transaction.commit().await?;
+
+    Ok(deleted)
+}
@@ -1,52 +1,306 @@
+use std::time::Duration;
+
+use anyhow::Context;
 use secrecy::{ExposeSecret, Secret};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use sqlx::ConnectOptions;
+
+use crate::domain::SubscriberEmail;
+use crate::email_client::EmailClient;
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, Clone)]
 pub struct Settings {
     pub database: DatabaseSettings,
-    pub application_port: u16,
+    pub application: ApplicationSettings,
+    pub email_client: EmailClientSettings,
+    pub issue_delivery: IssueDeliverySettings,
+    pub cleanup: CleanupSettings,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, Clone)]
+pub struct ApplicationSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+    pub host: String,
+    pub base_url: String,
+    pub hmac_secret: Secret<String>,
+    /// Signs session and flash message cookies. Must be at least 64 bytes long.
+    pub session_key: Secret<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
 pub struct DatabaseSettings {
     pub username: String,
     pub password: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
     pub database_name: String,
+    pub ssl_mode: SslMode,
+    /// Path to the CA certificate used to verify the server when `ssl_mode`
+    /// is `verify-ca` or `verify-full`.
+    pub ssl_root_cert: Option<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_connections: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub min_connections: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub acquire_timeout_seconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub idle_timeout_seconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_lifetime_seconds: u64,
+    /// Passed to Postgres as `statement_timeout` for every pooled connection.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub statement_timeout_milliseconds: u64,
+}
+
+/// How strictly we require TLS when talking to Postgres.
+/// Mirrors libpq's `sslmode` values.
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        }
+    }
 }
 
 impl DatabaseSettings {
-    pub fn connection_string(&self) -> Secret<String> {
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            self.username,
-            self.password.expose_secret(),
-            self.host,
-            self.port,
-            self.database_name
-        )
-        .into()
+    pub fn without_db(&self) -> PgConnectOptions {
+        let options = PgConnectOptions::new()
+            .host(&self.host)
+            .username(&self.username)
+            .password(self.password.expose_secret())
+            .port(self.port)
+            .ssl_mode(self.ssl_mode.into());
+
+        match &self.ssl_root_cert {
+            Some(path) => options.ssl_root_cert(path),
+            None => options,
+        }
     }
 
-    pub fn connection_string_without_db(&self) -> Secret<String> {
-        format!(
-            "postgres://{}:{}@{}:{}",
-            self.username,
-            self.password.expose_secret(),
-            self.host,
-            self.port
-        )
-        .into()
+    pub fn with_db(&self) -> PgConnectOptions {
+        self.without_db()
+            .database(&self.database_name)
+            .options([(
+                "statement_timeout",
+                format!("{}ms", self.statement_timeout_milliseconds),
+            )])
+            .log_statements(tracing::log::LevelFilter::Trace)
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct EmailClientSettings {
+    pub base_url: String,
+    pub sender_email: String,
+    pub authorization_token: Secret<String>,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_milliseconds: u64,
+}
+
+impl EmailClientSettings {
+    pub fn sender(&self) -> Result<SubscriberEmail, String> {
+        SubscriberEmail::parse(self.sender_email.clone())
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_milliseconds)
+    }
+
+    pub fn client(self) -> Result<EmailClient, anyhow::Error> {
+        let sender_email = self
+            .sender()
+            .map_err(anyhow::Error::msg)
+            .context("Invalid sender email address.")?;
+        let timeout = self.timeout();
+        Ok(EmailClient::new(
+            self.base_url,
+            sender_email,
+            self.authorization_token,
+            timeout,
+        ))
+    }
+}
+
+/// How the background worker retries newsletter deliveries that failed
+/// with a transient error.
+#[derive(serde::Deserialize, Clone)]
+pub struct IssueDeliverySettings {
+    /// Deliveries are moved to the dead-letter table after this many attempts.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_attempts: u16,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub initial_backoff_milliseconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_backoff_seconds: u64,
+}
+
+impl IssueDeliverySettings {
+    pub fn initial_backoff(&self) -> Duration {
+        Duration::from_millis(self.initial_backoff_milliseconds)
+    }
+
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_secs(self.max_backoff_seconds)
+    }
+}
+
+/// How often the cleanup task runs and how long the data it purges is kept.
+#[derive(serde::Deserialize, Clone)]
+pub struct CleanupSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub interval_seconds: u64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub idempotency_ttl_hours: u64,
+    /// Pending subscribers are purged once their latest confirmation token is older than this.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub subscription_token_ttl_hours: u64,
+}
+
+impl CleanupSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_seconds)
+    }
+
+    pub fn idempotency_ttl(&self) -> Duration {
+        Duration::from_secs(self.idempotency_ttl_hours * 60 * 60)
+    }
+
+    pub fn subscription_token_ttl(&self) -> Duration {
+        Duration::from_secs(self.subscription_token_ttl_hours * 60 * 60)
+    }
+}
+
+/// The possible runtime environments for our application.
+pub enum Environment {
+    Local,
+    Production,
+    Test,
+}
+
+impl Environment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Local => "local",
+            Environment::Production => "production",
+            Environment::Test => "test",
+        }
+    }
+}
+
+impl TryFrom<String> for Environment {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "production" => Ok(Self::Production),
+            "test" => Ok(Self::Test),
+            other => Err(format!(
+                "{} is not a supported environment. Use either `local`, `production` or `test`.",
+                other
+            )),
+        }
     }
 }
 
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
+    // Detect the running environment, defaulting to `local` if unspecified.
+    let environment: Environment = std::env::var("APP_ENVIRONMENT")
+        .unwrap_or_else(|_| "local".into())
+        .try_into()
+        .map_err(config::ConfigError::Message)?;
+
+    load_configuration(environment, std::env::vars().collect())
+}
+
+/// Layers the files for `environment` and the `APP_` entries of `variables`,
+/// which stand in for the process environment.
+pub fn load_configuration(
+    environment: Environment,
+    variables: config::Map<String, String>,
+) -> Result<Settings, config::ConfigError> {
+    let base_path = std::env::current_dir().expect("Failed to determine the current directory.");
+    let configuration_directory = base_path.join("configuration");
+    let environment_filename = format!("{}.yml", environment.as_str());
+
     let settings = config::Config::builder()
-        .add_source(config::File::new(
-            "configuration.yml",
-            config::FileFormat::Yaml,
+        .add_source(config::File::from(configuration_directory.join("base.yml")))
+        .add_source(config::File::from(
+            configuration_directory.join(environment_filename),
         ))
+        // Add in settings from environment variables (with a prefix of APP and '__' as separator)
+        // E.g. `APP_DATABASE__PASSWORD=secret` would set `Settings.database.password`
+        .add_source(
+            config::Environment::with_prefix("APP")
+                .prefix_separator("_")
+                .separator("__")
+                .source(Some(variables)),
+        )
         .build()?;
 
     settings.try_deserialize::<Settings>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{load_configuration, Environment};
+
+    fn production_variables() -> config::Map<String, String> {
+        [
+            (
+                "APP_APPLICATION__BASE_URL",
+                "https://newsletter.example.com",
+            ),
+            (
+                "APP_APPLICATION__HMAC_SECRET",
+                "a-production-only-secret-used-to-sign-unsubscribe-links",
+            ),
+            (
+                "APP_APPLICATION__SESSION_KEY",
+                "a-production-only-key-at-least-64-bytes-long-used-to-sign-session-cookies",
+            ),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn production_settings_load_when_secrets_are_provided() {
+        assert!(load_configuration(Environment::Production, production_variables()).is_ok());
+    }
+
+    #[test]
+    fn production_requires_the_hmac_secret_from_the_environment() {
+        let mut variables = production_variables();
+        variables.remove("APP_APPLICATION__HMAC_SECRET");
+
+        assert!(load_configuration(Environment::Production, variables).is_err());
+    }
+
+    #[test]
+    fn production_requires_the_session_key_from_the_environment() {
+        let mut variables = production_variables();
+        variables.remove("APP_APPLICATION__SESSION_KEY");
+
+        assert!(load_configuration(Environment::Production, variables).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+mod new_subscriber;
+mod subscriber_email;
+mod subscriber_name;
+mod unsubscribe_token;
+
+pub use new_subscriber::NewSubscriber;
+pub use subscriber_email::SubscriberEmail;
+pub use subscriber_name::SubscriberName;
+pub use unsubscribe_token::UnsubscribeToken;
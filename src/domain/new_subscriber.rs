@@ -0,0 +1,6 @@
+use crate::domain::{SubscriberEmail, SubscriberName};
+
+pub struct NewSubscriber {
+    pub email: SubscriberEmail,
+    pub name: SubscriberName,
+}
@@ -0,0 +1,85 @@
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// A per-subscriber token, signed with our HMAC secret, that lets the holder
+/// unsubscribe without logging in. It never expires: the link has to keep
+/// working for as long as the email sits in someone's inbox.
+#[derive(Debug)]
+pub struct UnsubscribeToken(String);
+
+impl UnsubscribeToken {
+    pub fn new(subscriber_id: Uuid, secret: &Secret<String>) -> Self {
+        let tag = mac(subscriber_id, secret).finalize().into_bytes();
+        Self(format!("{}.{}", subscriber_id, hex::encode(tag)))
+    }
+
+    /// Returns the id of the subscriber the token was issued for, if the
+    /// token is well formed and its signature checks out.
+    pub fn verify(token: &str, secret: &Secret<String>) -> Option<Uuid> {
+        let (subscriber_id, tag) = token.split_once('.')?;
+        let subscriber_id = Uuid::parse_str(subscriber_id).ok()?;
+        let tag = hex::decode(tag).ok()?;
+        mac(subscriber_id, secret)
+            .verify_slice(&tag)
+            .ok()
+            .map(|_| subscriber_id)
+    }
+}
+
+fn mac(subscriber_id: Uuid, secret: &Secret<String>) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose_secret().as_bytes())
+        .expect("HMAC can take keys of any size");
+    mac.update(subscriber_id.as_bytes());
+    mac
+}
+
+impl AsRef<str> for UnsubscribeToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnsubscribeToken;
+    use claims::{assert_none, assert_some_eq};
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    fn secret() -> Secret<String> {
+        Secret::new("a-very-secret-key".to_string())
+    }
+
+    #[test]
+    fn a_freshly_issued_token_is_verified() {
+        let subscriber_id = Uuid::new_v4();
+        let token = UnsubscribeToken::new(subscriber_id, &secret());
+        assert_some_eq!(
+            UnsubscribeToken::verify(token.as_ref(), &secret()),
+            subscriber_id
+        );
+    }
+
+    #[test]
+    fn a_token_signed_with_another_secret_is_rejected() {
+        let token = UnsubscribeToken::new(Uuid::new_v4(), &Secret::new("another-key".into()));
+        assert_none!(UnsubscribeToken::verify(token.as_ref(), &secret()));
+    }
+
+    #[test]
+    fn a_token_for_another_subscriber_is_rejected() {
+        let token = UnsubscribeToken::new(Uuid::new_v4(), &secret());
+        let (_, tag) = token.as_ref().split_once('.').unwrap();
+        let forged = format!("{}.{}", Uuid::new_v4(), tag);
+        assert_none!(UnsubscribeToken::verify(&forged, &secret()));
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        for token in ["", "not-a-token", "not-a-uuid.abcdef", "."] {
+            assert_none!(UnsubscribeToken::verify(token, &secret()));
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use rand::Rng;
+use secrecy::Secret;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{field::display, Span};
+use uuid::Uuid;
+
+use crate::configuration::{IssueDeliverySettings, Settings};
+use crate::domain::SubscriberEmail;
+use crate::email_client::{list_unsubscribe_headers, EmailClient, EmailClientError};
+use crate::routes::unsubscribe_link;
+use crate::startup::get_connection_pool;
+
+pub enum ExecutionOutcome {
+    TaskCompleted,
+    EmptyQueue,
+}
+
+pub async fn run_worker_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
+    let connection_pool = get_connection_pool(&configuration.database);
+    let email_client = configuration.email_client.client()?;
+    worker_loop(
+        connection_pool,
+        email_client,
+        configuration.application.base_url,
+        configuration.application.hmac_secret,
+        configuration.issue_delivery,
+    )
+    .await
+}
+
+async fn worker_loop(
+    pool: PgPool,
+    email_client: EmailClient,
+    base_url: String,
+    hmac_secret: Secret<String>,
+    settings: IssueDeliverySettings,
+) -> Result<(), anyhow::Error> {
+    loop {
+        match try_execute_task(&pool, &email_client, &base_url, &hmac_secret, &settings).await {
+            Ok(ExecutionOutcome::EmptyQueue) => {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Err(_) => {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            Ok(ExecutionOutcome::TaskCompleted) => {}
+        }
+    }
+}
+
+/// Attempts a single queued delivery. The task row stays locked, and therefore
+/// invisible to other workers, until the transaction that settles it commits:
+/// the task is either deleted, rescheduled or moved to the dead-letter table.
+#[tracing::instrument(
+    skip_all,
+    fields(
+        newsletter_issue_id=tracing::field::Empty,
+        subscriber_id=tracing::field::Empty
+    ),
+    err
+)]
+pub async fn try_execute_task(
+    pool: &PgPool,
+    email_client: &EmailClient,
+    base_url: &str,
+    hmac_secret: &Secret<String>,
+    settings: &IssueDeliverySettings,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let Some((mut transaction, task)) = dequeue_task(pool).await? else {
+        return Ok(ExecutionOutcome::EmptyQueue);
+    };
+    Span::current()
+        .record("newsletter_issue_id", display(task.newsletter_issue_id))
+        .record("subscriber_id", display(task.subscriber_id));
+
+    let outcome = match get_recipient(&mut transaction, task.subscriber_id).await? {
+        // They left the list after the issue was published.
+        None => Ok(()),
+        Some(recipient) => match SubscriberEmail::parse(recipient) {
+            Ok(email) => {
+                let issue = get_issue(&mut transaction, task.newsletter_issue_id).await?;
+                send_issue(email_client, &issue, &email, &task, base_url, hmac_secret).await
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Skipping a confirmed subscriber. \
+                    Their stored contact details are invalid",
+                );
+                Ok(())
+            }
+        },
+    };
+
+    match outcome {
+        Ok(()) => delete_task(&mut transaction, &task).await?,
+        Err(e) => {
+            let n_attempts = task.n_retries + 1;
+            if e.is_transient() && n_attempts < settings.max_attempts {
+                let delay = retry_delay(task.n_retries, settings);
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    n_attempts,
+                    retry_in_milliseconds = delay.as_millis() as u64,
+                    "Failed to deliver issue to a confirmed subscriber. \
+                    Retrying later.",
+                );
+                reschedule_task(&mut transaction, &task, delay).await?;
+            } else {
+                tracing::error!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    n_attempts,
+                    "Failed to deliver issue to a confirmed subscriber. \
+                    Moving it to the dead-letter table.",
+                );
+                dead_letter_task(&mut transaction, &task, n_attempts, &e).await?;
+            }
+        }
+    }
+    transaction.commit().await?;
+
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+async fn send_issue(
+    email_client: &EmailClient,
+    issue: &NewsletterIssue,
+    email: &SubscriberEmail,
+    task: &Task,
+    base_url: &str,
+    hmac_secret: &Secret<String>,
+) -> Result<(), EmailClientError> {
+    let unsubscribe_link = unsubscribe_link(base_url, task.subscriber_id, hmac_secret);
+    let html_body = format!(
+        "{}<hr /><a href=\"{}\">Unsubscribe</a>",
+        issue.html_content, unsubscribe_link
+    );
+    let text_body = format!(
+        "{}\n\n--\nUnsubscribe: {}",
+        issue.text_content, unsubscribe_link
+    );
+    email_client
+        .send_email_with_headers(
+            email,
+            &issue.title,
+            &html_body,
+            &text_body,
+            &list_unsubscribe_headers(&unsubscribe_link),
+        )
+        .await
+}
+
+/// Exponential backoff with jitter: the n-th retry waits somewhere between
+/// half and all of `initial_backoff * 2^n`, capped at `max_backoff`, so that
+/// deliveries failing together do not all retry at the same instant.
+fn retry_delay(n_retries: u16, settings: &IssueDeliverySettings) -> Duration {
+    let exponential = settings
+        .initial_backoff()
+        .saturating_mul(2u32.saturating_pow(n_retries.into()));
+    let capped = exponential.min(settings.max_backoff());
+    capped.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+type PgTransaction = Transaction<'static, Postgres>;
+
+struct Task {
+    newsletter_issue_id: Uuid,
+    subscriber_id: Uuid,
+    n_retries: u16,
+}
+
+#[tracing::instrument(skip_all)]
+async fn dequeue_task(pool: &PgPool) -> Result<Option<(PgTransaction, Task)>, anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+    let r = sqlx::query!(
+        r#"
+        SELECT newsletter_issue_id, subscriber_id, n_retries
+        FROM issue_delivery_queue
+        WHERE execute_after <= now()
+        FOR UPDATE
+        SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    if let Some(r) = r {
+        let task = Task {
+            newsletter_issue_id: r.newsletter_issue_id,
+            subscriber_id: r.subscriber_id,
+            n_retries: r.n_retries.try_into()?,
+        };
+        Ok(Some((transaction, task)))
+    } else {
+        Ok(None)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_task(transaction: &mut PgTransaction, task: &Task) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM issue_delivery_queue
+        WHERE
+            newsletter_issue_id = $1 AND
+            subscriber_id = $2
+        "#,
+        task.newsletter_issue_id,
+        task.subscriber_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn reschedule_task(
+    transaction: &mut PgTransaction,
+    task: &Task,
+    delay: Duration,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE issue_delivery_queue
+        SET
+            n_retries = n_retries + 1,
+            execute_after = now() + make_interval(secs => $3)
+        WHERE
+            newsletter_issue_id = $1 AND
+            subscriber_id = $2
+        "#,
+        task.newsletter_issue_id,
+        task.subscriber_id,
+        delay.as_secs_f64()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn dead_letter_task(
+    transaction: &mut PgTransaction,
+    task: &Task,
+    n_attempts: u16,
+    error: &EmailClientError,
+) -> Result<(), anyhow::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO issue_delivery_dead_letters (
+            newsletter_issue_id,
+            subscriber_id,
+            n_attempts,
+            last_error,
+            failed_at
+        )
+        VALUES ($1, $2, $3, $4, now())
+        "#,
+        task.newsletter_issue_id,
+        task.subscriber_id,
+        i16::try_from(n_attempts)?,
+        error.to_string()
+    )
+    .execute(&mut **transaction)
+    .await?;
+    delete_task(transaction, task).await
+}
+
+/// The email address to deliver to, if the subscriber is still confirmed.
+#[tracing::instrument(skip_all)]
+async fn get_recipient(
+    transaction: &mut PgTransaction,
+    subscriber_id: Uuid,
+) -> Result<Option<String>, anyhow::Error> {
+    let recipient = sqlx::query!(
+        r#"
+        SELECT email
+        FROM subscriptions
+        WHERE id = $1 AND status = 'confirmed'
+        "#,
+        subscriber_id
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+    Ok(recipient.map(|r| r.email))
+}
+
+struct NewsletterIssue {
+    title: String,
+    text_content: String,
+    html_content: String,
+}
+
+#[tracing::instrument(skip_all)]
+async fn get_issue(
+    transaction: &mut PgTransaction,
+    issue_id: Uuid,
+) -> Result<NewsletterIssue, anyhow::Error> {
+    let issue = sqlx::query_as!(
+        NewsletterIssue,
+        r#"
+        SELECT title, text_content, html_content
+        FROM newsletter_issues
+        WHERE
+            newsletter_issue_id = $1
+        "#,
+        issue_id
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+    Ok(issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::retry_delay;
+    use crate::configuration::IssueDeliverySettings;
+
+    fn settings() -> IssueDeliverySettings {
+        IssueDeliverySettings {
+            max_attempts: 10,
+            initial_backoff_milliseconds: 1000,
+            max_backoff_seconds: 60,
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_with_every_retry() {
+        for (n_retries, upper_bound) in [(0, 1), (1, 2), (2, 4), (3, 8)] {
+            let upper_bound = Duration::from_secs(upper_bound);
+            let delay = retry_delay(n_retries, &settings());
+            assert!(delay <= upper_bound, "{:?} > {:?}", delay, upper_bound);
+            assert!(
+                delay >= upper_bound / 2,
+                "{:?} < {:?}",
+                delay,
+                upper_bound / 2
+            );
+        }
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        for n_retries in [6, 20, u16::MAX] {
+            let delay = retry_delay(n_retries, &settings());
+            assert!(delay <= Duration::from_secs(60));
+            assert!(delay >= Duration::from_secs(30));
+        }
+    }
+}
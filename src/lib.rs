@@ -1,7 +1,23 @@
+pub mod authentication;
+
+pub mod cleanup_worker;
+
 pub mod configuration;
 
+pub mod domain;
+
+pub mod email_client;
+
+pub mod idempotency;
+
+pub mod issue_delivery_worker;
+
 pub mod routes;
 
+pub mod session;
+
 pub mod startup;
 
 pub mod telemetry;
+
+pub mod utils;
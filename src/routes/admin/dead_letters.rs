@@ -0,0 +1,83 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::utils::e500;
+
+#[derive(serde::Serialize)]
+pub struct DeadLetter {
+    newsletter_issue_id: Uuid,
+    title: String,
+    subscriber_id: Uuid,
+    subscriber_email: String,
+    n_attempts: i16,
+    last_error: String,
+    failed_at: DateTime<Utc>,
+}
+
+/// Deliveries the worker gave up on, most recent failures first.
+#[tracing::instrument(name = "List dead-lettered deliveries", skip(pool))]
+pub async fn list_dead_letters(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
+    let dead_letters = sqlx::query_as!(
+        DeadLetter,
+        r#"
+        SELECT
+            d.newsletter_issue_id,
+            i.title,
+            d.subscriber_id,
+            s.email AS subscriber_email,
+            d.n_attempts,
+            d.last_error,
+            d.failed_at
+        FROM issue_delivery_dead_letters d
+        JOIN newsletter_issues i USING (newsletter_issue_id)
+        JOIN subscriptions s ON s.id = d.subscriber_id
+        ORDER BY d.failed_at DESC
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(e500)?;
+
+    Ok(HttpResponse::Ok().json(dead_letters))
+}
+
+/// Puts a dead-lettered delivery back in the queue with a fresh retry budget.
+#[tracing::instrument(name = "Requeue a dead-lettered delivery", skip(pool))]
+pub async fn requeue_dead_letter(
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (newsletter_issue_id, subscriber_id) = path.into_inner();
+    let mut transaction = pool.begin().await.map_err(e500)?;
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM issue_delivery_dead_letters
+        WHERE newsletter_issue_id = $1 AND subscriber_id = $2
+        "#,
+        newsletter_issue_id,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(e500)?;
+    if deleted.rows_affected() == 0 {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    sqlx::query!(
+        r#"
+        INSERT INTO issue_delivery_queue (newsletter_issue_id, subscriber_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+        newsletter_issue_id,
+        subscriber_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(e500)?;
+    transaction.commit().await.map_err(e500)?;
+
+    Ok(HttpResponse::Accepted().finish())
+}
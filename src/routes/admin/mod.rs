@@ -0,0 +1,11 @@
+mod dashboard;
+mod dead_letters;
+mod logout;
+mod password;
+mod subscribers;
+
+pub use dashboard::admin_dashboard;
+pub use dead_letters::*;
+pub use logout::log_out;
+pub use password::*;
+pub use subscribers::*;
@@ -0,0 +1,230 @@
+use actix_multipart::{Field, Multipart};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use csv_async::{AsyncReaderBuilder, Trim};
+use futures_util::{StreamExt, TryStreamExt};
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
+use crate::routes::error_chain_fmt;
+
+const BATCH_SIZE: usize = 1000;
+
+#[derive(serde::Deserialize)]
+struct CsvRow {
+    email: String,
+    name: String,
+    subscribed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize, Default)]
+pub struct ImportReport {
+    imported: u64,
+    /// Rows whose email address was already on the list, in the table or
+    /// earlier in the file.
+    skipped_duplicates: u64,
+    rejected: Vec<RejectedLine>,
+}
+
+#[derive(serde::Serialize)]
+pub struct RejectedLine {
+    line: u64,
+    reason: String,
+}
+
+struct ImportedSubscriber {
+    subscriber: NewSubscriber,
+    subscribed_at: DateTime<Utc>,
+}
+
+#[derive(thiserror::Error)]
+pub enum ImportError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ImportError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ImportError::ValidationError(message) => {
+                HttpResponse::BadRequest().body(message.clone())
+            }
+            ImportError::UnexpectedError(_) => HttpResponse::new(self.status_code()),
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ImportError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ImportError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Imports the CSV uploaded in the `file` field of a multipart form.
+///
+/// The file needs `email` and `name` columns, and may have a `subscribed_at`
+/// one (RFC 3339). Imported contacts are confirmed straight away: they opted
+/// in with the provider we are migrating from. Addresses we already know are
+/// left untouched, so people who unsubscribed stay unsubscribed.
+///
+/// The whole upload is parsed and validated before we touch the database, and
+/// the import is all-or-nothing: rows are inserted in batches within a single
+/// transaction.
+#[tracing::instrument(
+    name = "Import subscribers",
+    skip(payload, pool),
+    fields(imported = tracing::field::Empty, rejected = tracing::field::Empty)
+)]
+pub async fn import_subscribers(
+    mut payload: Multipart,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ImportError> {
+    let field = loop {
+        let field = payload
+            .try_next()
+            .await
+            .map_err(|e| ImportError::ValidationError(e.to_string()))?
+            .ok_or_else(|| ImportError::ValidationError("The 'file' field is missing".into()))?;
+        if field.name() == "file" {
+            break field;
+        }
+    };
+
+    // Multipart fields are not `Send`, which the CSV reader requires: we pipe
+    // the upload through an in-memory channel and parse the other end.
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let (upload, parsed) = tokio::join!(forward_upload(field, writer), parse_csv(reader));
+    let (subscribers, mut report) = parsed?;
+    upload.context("Failed to read the uploaded file")?;
+    store_subscribers(&pool, &subscribers, &mut report).await?;
+
+    tracing::Span::current()
+        .record("imported", report.imported)
+        .record("rejected", report.rejected.len());
+    Ok(HttpResponse::Ok().json(report))
+}
+
+async fn forward_upload(
+    mut field: Field,
+    mut writer: tokio::io::DuplexStream,
+) -> Result<(), anyhow::Error> {
+    // `MultipartError` is not `Send` + `Sync`, therefore it doesn't play nicely with `anyhow`
+    while let Some(chunk) = field
+        .try_next()
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+    {
+        writer.write_all(&chunk).await?;
+    }
+    // Dropping the writer signals the end of the file to the reader.
+    Ok(())
+}
+
+async fn parse_csv(
+    reader: impl AsyncRead + Unpin + Send,
+) -> Result<(Vec<ImportedSubscriber>, ImportReport), ImportError> {
+    let mut csv = AsyncReaderBuilder::new()
+        .trim(Trim::All)
+        .create_deserializer(reader);
+    let headers = csv
+        .headers()
+        .await
+        .map_err(|e| ImportError::ValidationError(e.to_string()))?;
+    for required in ["email", "name"] {
+        if !headers.iter().any(|h| h == required) {
+            return Err(ImportError::ValidationError(format!(
+                "The CSV header must have an '{required}' column"
+            )));
+        }
+    }
+
+    let mut report = ImportReport::default();
+    let mut subscribers = Vec::new();
+    let mut rows = csv.deserialize_with_pos::<CsvRow>();
+    while let Some((row, position)) = rows.next().await {
+        match row.map_err(|e| e.to_string()).and_then(parse_row) {
+            Ok(subscriber) => subscribers.push(subscriber),
+            Err(reason) => report.rejected.push(RejectedLine {
+                line: position.line(),
+                reason,
+            }),
+        }
+    }
+
+    Ok((subscribers, report))
+}
+
+fn parse_row(row: CsvRow) -> Result<ImportedSubscriber, String> {
+    Ok(ImportedSubscriber {
+        subscriber: NewSubscriber {
+            email: SubscriberEmail::parse(row.email)?,
+            name: SubscriberName::parse(row.name)?,
+        },
+        subscribed_at: row.subscribed_at.unwrap_or_else(Utc::now),
+    })
+}
+
+async fn store_subscribers(
+    pool: &PgPool,
+    subscribers: &[ImportedSubscriber],
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    for batch in subscribers.chunks(BATCH_SIZE) {
+        insert_batch(&mut transaction, batch, report).await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit SQL transaction to import subscribers")?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(batch_size = batch.len()))]
+async fn insert_batch(
+    transaction: &mut Transaction<'_, Postgres>,
+    batch: &[ImportedSubscriber],
+    report: &mut ImportReport,
+) -> Result<(), anyhow::Error> {
+    let ids: Vec<Uuid> = batch.iter().map(|_| Uuid::new_v4()).collect();
+    let emails: Vec<&str> = batch.iter().map(|s| s.subscriber.email.as_ref()).collect();
+    let names: Vec<&str> = batch.iter().map(|s| s.subscriber.name.as_ref()).collect();
+    let subscribed_ats: Vec<DateTime<Utc>> = batch.iter().map(|s| s.subscribed_at).collect();
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status, source)
+        SELECT id, email, name, subscribed_at, 'confirmed', 'import'
+        FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::timestamptz[])
+            AS t(id, email, name, subscribed_at)
+        ON CONFLICT (email) DO NOTHING
+        "#,
+        &ids,
+        &emails as &[&str],
+        &names as &[&str],
+        &subscribed_ats
+    )
+    .execute(&mut **transaction)
+    .await
+    .context("Failed to insert a batch of imported subscribers")?
+    .rows_affected();
+
+    report.imported += inserted;
+    report.skipped_duplicates += batch.len() as u64 - inserted;
+    Ok(())
+}
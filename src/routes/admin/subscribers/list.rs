@@ -0,0 +1,235 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::routes::error_chain_fmt;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(serde::Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    PendingConfirmation,
+    Confirmed,
+    Unsubscribed,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::PendingConfirmation => "pending_confirmation",
+            SubscriptionStatus::Confirmed => "confirmed",
+            SubscriptionStatus::Unsubscribed => "unsubscribed",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ListSubscribersParameters {
+    status: Option<SubscriptionStatus>,
+    /// Inclusive lower bound on `subscribed_at`.
+    subscribed_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `subscribed_at`.
+    subscribed_before: Option<DateTime<Utc>>,
+    /// Case-insensitive prefix of the email address or of the name.
+    search: Option<String>,
+    limit: Option<i64>,
+    /// The `next_cursor` of the previous page.
+    cursor: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Subscriber {
+    id: Uuid,
+    email: String,
+    name: String,
+    status: String,
+    source: String,
+    subscribed_at: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SubscribersPage {
+    subscribers: Vec<Subscriber>,
+    /// Absent on the last page.
+    next_cursor: Option<String>,
+}
+
+/// Position of the last subscriber of a page in the `(subscribed_at, id)` ordering.
+///
+/// Handed out as an opaque string: clients should not build their own.
+#[derive(Debug)]
+struct Cursor {
+    subscribed_at: DateTime<Utc>,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        let raw = format!(
+            "{}/{}",
+            self.subscribed_at
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.id
+        );
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    fn decode(s: &str) -> Result<Self, anyhow::Error> {
+        let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(s)?;
+        let raw = String::from_utf8(raw)?;
+        let (subscribed_at, id) = raw
+            .split_once('/')
+            .context("The cursor is missing its separator")?;
+        Ok(Self {
+            subscribed_at: DateTime::parse_from_rfc3339(subscribed_at)?.with_timezone(&Utc),
+            id: id.parse()?,
+        })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum ListSubscribersError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ListSubscribersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ListSubscribersError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ListSubscribersError::ValidationError(message) => {
+                HttpResponse::BadRequest().body(message.clone())
+            }
+            ListSubscribersError::UnexpectedError(_) => HttpResponse::new(self.status_code()),
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ListSubscribersError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ListSubscribersError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Most recent subscribers first, one page at a time.
+///
+/// Pages are keyed on the last `(subscribed_at, id)` seen rather than on an
+/// offset, so they stay cheap deep into the table and don't shift when people
+/// sign up while support is browsing.
+#[tracing::instrument(name = "List subscribers", skip(pool))]
+pub async fn list_subscribers(
+    parameters: web::Query<ListSubscribersParameters>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ListSubscribersError> {
+    let limit = parameters.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ListSubscribersError::ValidationError(format!(
+            "The limit must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+    let cursor = parameters
+        .cursor
+        .as_deref()
+        .map(Cursor::decode)
+        .transpose()
+        .map_err(|_| ListSubscribersError::ValidationError("Invalid cursor".into()))?;
+    let search_pattern = parameters
+        .search
+        .as_deref()
+        .map(|s| format!("{}%", escape_like_pattern(&s.to_lowercase())));
+
+    let mut subscribers = sqlx::query_as!(
+        Subscriber,
+        r#"
+        SELECT id, email, name, status, source, subscribed_at
+        FROM subscriptions
+        WHERE
+            ($1::text IS NULL OR status = $1) AND
+            ($2::timestamptz IS NULL OR subscribed_at >= $2) AND
+            ($3::timestamptz IS NULL OR subscribed_at < $3) AND
+            ($4::text IS NULL OR lower(email) LIKE $4 OR lower(name) LIKE $4) AND
+            ($5::timestamptz IS NULL OR (subscribed_at, id) < ($5, $6::uuid))
+        ORDER BY subscribed_at DESC, id DESC
+        LIMIT $7
+        "#,
+        parameters.status.map(|s| s.as_str()),
+        parameters.subscribed_after,
+        parameters.subscribed_before,
+        search_pattern,
+        cursor.as_ref().map(|c| c.subscribed_at),
+        cursor.as_ref().map(|c| c.id),
+        // One extra row tells us whether there is a next page.
+        limit + 1
+    )
+    .fetch_all(pool.get_ref())
+    .await
+    .context("Failed to fetch subscribers")?;
+
+    let next_cursor = if subscribers.len() as i64 > limit {
+        subscribers.truncate(limit as usize);
+        subscribers.last().map(|s| {
+            Cursor {
+                subscribed_at: s.subscribed_at,
+                id: s.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(SubscribersPage {
+        subscribers,
+        next_cursor,
+    }))
+}
+
+/// Makes user input match literally inside a `LIKE` pattern.
+fn escape_like_pattern(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_like_pattern, Cursor};
+    use chrono::Utc;
+    use claims::{assert_err, assert_ok};
+    use uuid::Uuid;
+
+    #[test]
+    fn a_cursor_survives_a_round_trip() {
+        let cursor = Cursor {
+            subscribed_at: Utc::now(),
+            id: Uuid::new_v4(),
+        };
+        let decoded = assert_ok!(Cursor::decode(&cursor.encode()));
+        assert_eq!(decoded.subscribed_at, cursor.subscribed_at);
+        assert_eq!(decoded.id, cursor.id);
+    }
+
+    #[test]
+    fn a_tampered_cursor_is_rejected() {
+        assert_err!(Cursor::decode("not-a-cursor"));
+    }
+
+    #[test]
+    fn like_wildcards_are_escaped() {
+        assert_eq!(escape_like_pattern(r"50%_off\"), r"50\%\_off\\");
+    }
+}
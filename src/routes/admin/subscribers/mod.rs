@@ -0,0 +1,7 @@
+mod export;
+mod import;
+mod list;
+
+pub use export::*;
+pub use import::*;
+pub use list::*;
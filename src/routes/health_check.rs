@@ -1,5 +1,18 @@
-use actix_web::HttpResponse;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
 
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
+
+/// Unlike `health_check`, only reports success once the database is reachable.
+#[tracing::instrument(name = "Checking readiness", skip(pool))]
+pub async fn readiness_check(pool: web::Data<PgPool>) -> HttpResponse {
+    match sqlx::query("SELECT 1").execute(pool.get_ref()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(err) => {
+            tracing::warn!("Database is not reachable: {:?}", err);
+            HttpResponse::ServiceUnavailable().finish()
+        }
+    }
+}
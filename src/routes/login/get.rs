@@ -0,0 +1,24 @@
+use actix_web::HttpResponse;
+use actix_web_flash_messages::IncomingFlashMessages;
+
+use crate::utils::{flash_messages_html, html_page};
+
+pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
+    let messages_html = flash_messages_html(&flash_messages);
+
+    html_page(
+        "Login",
+        &format!(
+            r#"{messages_html}
+    <form action="/login" method="post">
+        <label>Username
+            <input type="text" placeholder="Enter Username" name="username">
+        </label>
+        <label>Password
+            <input type="password" placeholder="Enter Password" name="password">
+        </label>
+        <button type="submit">Login</button>
+    </form>"#
+        ),
+    )
+}
@@ -1,51 +1,348 @@
-use actix_web::{web, web::Form, HttpResponse};
+use actix_web::http::StatusCode;
+use actix_web::{web, web::Form, HttpResponse, ResponseError};
 use chrono::Utc;
-use sqlx::PgPool;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use secrecy::Secret;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
+use crate::email_client::{list_unsubscribe_headers, EmailClient, EmailClientError};
+use crate::routes::unsubscribe_link;
+use crate::startup::{ApplicationBaseUrl, HmacSecret};
+
 #[derive(serde::Deserialize)]
 pub struct FormData {
     email: String,
     name: String,
 }
 
+impl TryFrom<FormData> for NewSubscriber {
+    type Error = String;
+
+    fn try_from(value: FormData) -> Result<Self, Self::Error> {
+        let name = SubscriberName::parse(value.name)?;
+        let email = SubscriberEmail::parse(value.email)?;
+        Ok(Self { email, name })
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum SubscribeError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Failed to acquire a Postgres connection from the pool.")]
+    PoolError(#[source] sqlx::Error),
+    #[error("Failed to insert new subscriber in the database.")]
+    InsertSubscriberError(#[source] sqlx::Error),
+    #[error("Failed to look up an existing subscriber.")]
+    LookupSubscriberError(#[source] sqlx::Error),
+    #[error("Failed to store the confirmation token for a new subscriber.")]
+    StoreTokenError(#[source] sqlx::Error),
+    #[error("Failed to commit SQL transaction to store a new subscriber.")]
+    TransactionCommitError(#[source] sqlx::Error),
+}
+
+impl std::fmt::Debug for SubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for SubscribeError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            SubscribeError::ValidationError(message) => {
+                HttpResponse::BadRequest().body(message.clone())
+            }
+            // The cause chain goes to the logs, not to the client.
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SubscribeError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            SubscribeError::PoolError(_)
+            | SubscribeError::InsertSubscriberError(_)
+            | SubscribeError::LookupSubscriberError(_)
+            | SubscribeError::StoreTokenError(_)
+            | SubscribeError::TransactionCommitError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Writes `e` followed by every error in its `source()` chain, one per line.
+pub fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
+
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(form, pool),
+    skip(form, pool, email_client, base_url, hmac_secret),
     fields(
         // request_id = %Uuid::new_v4(),
         subscriber_email = %form.email,
         subscriber_name = %form.name,
     )
 )]
-pub async fn subscribe(form: Form<FormData>, pool: web::Data<PgPool>) -> HttpResponse {
-    match insert_subscriber(&pool, &form).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(err) => {
-            tracing::error!("Failed to execute query: {:?}", err);
-            HttpResponse::InternalServerError().finish()
+pub async fn subscribe(
+    form: Form<FormData>,
+    pool: web::Data<PgPool>,
+    email_client: web::Data<EmailClient>,
+    base_url: web::Data<ApplicationBaseUrl>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> Result<HttpResponse, SubscribeError> {
+    let new_subscriber = form.0.try_into().map_err(SubscribeError::ValidationError)?;
+
+    let mut transaction = pool.begin().await.map_err(SubscribeError::PoolError)?;
+    let pending_subscriber_id = match insert_subscriber(&mut transaction, &new_subscriber)
+        .await
+        .map_err(SubscribeError::InsertSubscriberError)?
+    {
+        Some(subscriber_id) => Some(subscriber_id),
+        None => {
+            let existing = get_existing_subscriber(&mut transaction, &new_subscriber.email)
+                .await
+                .map_err(SubscribeError::LookupSubscriberError)?;
+            match existing.status.as_str() {
+                // Already on the list: there is nothing left to confirm.
+                "confirmed" => None,
+                status => {
+                    // Coming back after leaving the list: opt in again from scratch.
+                    if status == "unsubscribed" {
+                        mark_subscriber_as_pending(&mut transaction, existing.id)
+                            .await
+                            .map_err(SubscribeError::InsertSubscriberError)?;
+                    }
+                    // Rotate the token and send a fresh confirmation email.
+                    delete_subscription_tokens(&mut transaction, existing.id)
+                        .await
+                        .map_err(SubscribeError::StoreTokenError)?;
+                    Some(existing.id)
+                }
+            }
         }
+    };
+
+    let pending_confirmation = match pending_subscriber_id {
+        Some(subscriber_id) => {
+            let subscription_token = generate_subscription_token();
+            store_token(&mut transaction, subscriber_id, &subscription_token)
+                .await
+                .map_err(SubscribeError::StoreTokenError)?;
+            Some((subscriber_id, subscription_token))
+        }
+        None => None,
+    };
+
+    // Nothing is visible to other connections until every row above is written.
+    transaction
+        .commit()
+        .await
+        .map_err(SubscribeError::TransactionCommitError)?;
+
+    // The email goes out after the response, so answering takes as long for an
+    // address that is already confirmed as for a new sign-up: response times
+    // do not reveal who is on the list.
+    if let Some((subscriber_id, subscription_token)) = pending_confirmation {
+        tokio::spawn(
+            async move {
+                if let Err(e) = send_confirmation_email(
+                    &email_client,
+                    new_subscriber,
+                    subscriber_id,
+                    &base_url.0,
+                    &subscription_token,
+                    &hmac_secret.0,
+                )
+                .await
+                {
+                    tracing::error!(
+                        error.cause_chain = ?e,
+                        error.message = %e,
+                        "Failed to send a confirmation email.",
+                    );
+                }
+            }
+            .in_current_span(),
+        );
     }
+
+    Ok(HttpResponse::Ok().finish())
 }
 
-#[tracing::instrument(name = "Saving new subscriber details in DB.", skip(form, pool))]
-pub async fn insert_subscriber(pool: &PgPool, form: &FormData) -> Result<(), sqlx::Error> {
-    sqlx::query!(
+#[tracing::instrument(
+    name = "Send a confirmation email to a new subscriber",
+    skip(
+        email_client,
+        new_subscriber,
+        base_url,
+        subscription_token,
+        hmac_secret
+    )
+)]
+pub async fn send_confirmation_email(
+    email_client: &EmailClient,
+    new_subscriber: NewSubscriber,
+    subscriber_id: Uuid,
+    base_url: &str,
+    subscription_token: &str,
+    hmac_secret: &Secret<String>,
+) -> Result<(), EmailClientError> {
+    let confirmation_link = format!(
+        "{}/subscriptions/confirm?subscription_token={}",
+        base_url, subscription_token
+    );
+    let html_body = format!(
+        "Welcome to our newsletter!<br />\
+        Click <a href=\"{}\">here</a> to confirm your subscription.",
+        confirmation_link
+    );
+    let plain_body = format!(
+        "Welcome to our newsletter!\nVisit {} to confirm your subscription.",
+        confirmation_link
+    );
+
+    let unsubscribe_link = unsubscribe_link(base_url, subscriber_id, hmac_secret);
+
+    email_client
+        .send_email_with_headers(
+            &new_subscriber.email,
+            "Welcome!",
+            &html_body,
+            &plain_body,
+            &list_unsubscribe_headers(&unsubscribe_link),
+        )
+        .await
+}
+
+/// Returns `None` if a subscriber with the same email address already exists.
+#[tracing::instrument(
+    name = "Saving new subscriber details in DB.",
+    skip(new_subscriber, transaction)
+)]
+pub async fn insert_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    new_subscriber: &NewSubscriber,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let result = sqlx::query!(
         r#"
-    INSERT INTO subscriptions (id, email, name, subscribed_at)
-    VALUES ($1, $2, $3, $4)
+    INSERT INTO subscriptions (id, email, name, subscribed_at, status, source)
+    VALUES ($1, $2, $3, $4, 'pending_confirmation', 'signup')
+    ON CONFLICT (email) DO NOTHING
+    RETURNING id
     "#,
         Uuid::new_v4(),
-        form.email,
-        form.name,
+        new_subscriber.email.as_ref(),
+        new_subscriber.name.as_ref(),
         Utc::now()
     )
-    .execute(pool)
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(result.map(|r| r.id))
+}
+
+pub struct ExistingSubscriber {
+    pub id: Uuid,
+    pub status: String,
+}
+
+#[tracing::instrument(name = "Get existing subscriber by email", skip(email, transaction))]
+pub async fn get_existing_subscriber(
+    transaction: &mut Transaction<'_, Postgres>,
+    email: &SubscriberEmail,
+) -> Result<ExistingSubscriber, sqlx::Error> {
+    // Lock the row so concurrent sign-ups for the same address rotate tokens one at a time.
+    sqlx::query_as!(
+        ExistingSubscriber,
+        r#"SELECT id, status FROM subscriptions WHERE email = $1 FOR UPDATE"#,
+        email.as_ref(),
+    )
+    .fetch_one(&mut **transaction)
     .await
-    .map_err(|e| {
-        tracing::error!("Failed to execute query: {:?}", e);
-        e
-    })?;
+}
+
+#[tracing::instrument(
+    name = "Mark unsubscribed subscriber as pending",
+    skip(transaction, subscriber_id)
+)]
+pub async fn mark_subscriber_as_pending(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    UPDATE subscriptions
+    SET status = 'pending_confirmation', unsubscribed_at = NULL
+    WHERE id = $1
+    "#,
+        subscriber_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
 
     Ok(())
 }
+
+#[tracing::instrument(
+    name = "Delete existing subscription tokens",
+    skip(transaction, subscriber_id)
+)]
+pub async fn delete_subscription_tokens(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1"#,
+        subscriber_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(
+    name = "Store subscription token in the database",
+    skip(subscription_token, transaction)
+)]
+pub async fn store_token(
+    transaction: &mut Transaction<'_, Postgres>,
+    subscriber_id: Uuid,
+    subscription_token: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+    INSERT INTO subscription_tokens (subscription_token, subscriber_id)
+    VALUES ($1, $2)
+    "#,
+        subscription_token,
+        subscriber_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Generate a random 25-characters-long case-sensitive subscription token.
+fn generate_subscription_token() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(25)
+        .collect()
+}
@@ -0,0 +1,104 @@
+use actix_web::{web, HttpResponse};
+use secrecy::Secret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::UnsubscribeToken;
+use crate::startup::HmacSecret;
+use crate::utils::{e500, html_page};
+
+#[derive(serde::Deserialize)]
+pub struct UnsubscribeParameters {
+    token: String,
+}
+
+/// The link we put in every email, both in the body and in `List-Unsubscribe`.
+pub fn unsubscribe_link(base_url: &str, subscriber_id: Uuid, secret: &Secret<String>) -> String {
+    let token = UnsubscribeToken::new(subscriber_id, secret);
+    format!(
+        "{}/subscriptions/unsubscribe?token={}",
+        base_url,
+        token.as_ref()
+    )
+}
+
+/// Asks for confirmation instead of unsubscribing straight away: link scanners
+/// and prefetchers follow GET links in emails, so only the POST has side effects.
+#[tracing::instrument(name = "Show unsubscribe form", skip(parameters, hmac_secret))]
+pub async fn unsubscribe_form(
+    parameters: web::Query<UnsubscribeParameters>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> HttpResponse {
+    if UnsubscribeToken::verify(&parameters.token, &hmac_secret.0).is_none() {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    html_page(
+        "Unsubscribe",
+        &format!(
+            r#"<p>Do you want to stop receiving our newsletter?</p>
+    <form action="/subscriptions/unsubscribe?token={}" method="post">
+        <button type="submit">Unsubscribe</button>
+    </form>"#,
+            parameters.token
+        ),
+    )
+}
+
+/// Also serves RFC 8058 one-click requests: mail clients POST
+/// `List-Unsubscribe=One-Click` to the URL in `List-Unsubscribe`, and the body
+/// carries nothing we need.
+#[tracing::instrument(
+    name = "Unsubscribe a subscriber",
+    skip(parameters, pool, hmac_secret),
+    fields(subscriber_id = tracing::field::Empty)
+)]
+pub async fn unsubscribe(
+    parameters: web::Query<UnsubscribeParameters>,
+    pool: web::Data<PgPool>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let subscriber_id = match UnsubscribeToken::verify(&parameters.token, &hmac_secret.0) {
+        Some(subscriber_id) => subscriber_id,
+        None => return Ok(HttpResponse::Unauthorized().finish()),
+    };
+    tracing::Span::current().record("subscriber_id", tracing::field::display(&subscriber_id));
+
+    mark_subscriber_as_unsubscribed(&pool, subscriber_id)
+        .await
+        .map_err(e500)?;
+
+    Ok(html_page(
+        "Unsubscribed",
+        "<p>You have been unsubscribed and will not receive further emails.</p>",
+    ))
+}
+
+#[tracing::instrument(name = "Mark subscriber as unsubscribed", skip(pool))]
+pub async fn mark_subscriber_as_unsubscribed(
+    pool: &PgPool,
+    subscriber_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    // Leave the original timestamp alone if the subscriber already left.
+    sqlx::query!(
+        r#"
+    UPDATE subscriptions
+    SET status = 'unsubscribed', unsubscribed_at = now()
+    WHERE id = $1 AND status != 'unsubscribed'
+    "#,
+        subscriber_id,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // A pending confirmation link must not sign them back up.
+    sqlx::query!(
+        r#"DELETE FROM subscription_tokens WHERE subscriber_id = $1"#,
+        subscriber_id,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,5 @@
+mod state;
+mod store;
+
+pub use state::TypedSession;
+pub use store::PostgresSessionStore;
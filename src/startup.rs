@@ -1,20 +1,161 @@
 use std::net::TcpListener;
+use std::time::Duration;
 
+use actix_session::SessionMiddleware;
+use actix_web::cookie::Key;
 use actix_web::dev::Server;
 use actix_web::{web, App, HttpServer};
+use actix_web_flash_messages::storage::CookieMessageStore;
+use actix_web_flash_messages::FlashMessagesFramework;
+use actix_web_lab::middleware::from_fn;
+use anyhow::Context;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use tracing_actix_web::TracingLogger;
 
-use crate::routes::{health_check, subscribe};
+use crate::authentication::reject_anonymous_users;
+use crate::configuration::{DatabaseSettings, Settings};
+use crate::email_client::EmailClient;
+use crate::routes::{
+    admin_dashboard, change_password, change_password_form, confirm, export_subscribers,
+    health_check, import_subscribers, list_dead_letters, list_subscribers, log_out, login,
+    login_form, publish_newsletter, readiness_check, requeue_dead_letter, subscribe, unsubscribe,
+    unsubscribe_form,
+};
+use crate::session::PostgresSessionStore;
 
-pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
+/// A fully wired HTTP server, bound to its listener and ready to be run.
+pub struct Application {
+    port: u16,
+    server: Server,
+}
+
+impl Application {
+    pub async fn build(configuration: Settings) -> Result<Self, anyhow::Error> {
+        let connection_pool = get_connection_pool(&configuration.database);
+
+        let email_client = configuration.email_client.client()?;
+
+        let address = format!(
+            "{}:{}",
+            configuration.application.host, configuration.application.port
+        );
+        let listener = TcpListener::bind(address)?;
+        // The configured port may be 0, in which case the OS picks one for us.
+        let port = listener.local_addr()?.port();
+
+        let session_key = Key::try_from(
+            configuration
+                .application
+                .session_key
+                .expose_secret()
+                .as_bytes(),
+        )
+        .context("The session key must be at least 64 bytes long")?;
+
+        let server = run(
+            listener,
+            connection_pool,
+            email_client,
+            configuration.application.base_url,
+            configuration.application.hmac_secret,
+            session_key,
+            configuration.cleanup.subscription_token_ttl(),
+        )?;
+
+        Ok(Self { port, server })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
+        self.server.await
+    }
+}
+
+/// Builds the pool without connecting: connections are established on first use,
+/// so the server can start (and report itself as not ready) while Postgres is down.
+pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(configuration.max_connections)
+        .min_connections(configuration.min_connections)
+        .acquire_timeout(Duration::from_secs(configuration.acquire_timeout_seconds))
+        .idle_timeout(Duration::from_secs(configuration.idle_timeout_seconds))
+        .max_lifetime(Duration::from_secs(configuration.max_lifetime_seconds))
+        .connect_lazy_with(configuration.with_db())
+}
+
+/// The public URL the application is reachable at, used to build links we hand out.
+pub struct ApplicationBaseUrl(pub String);
+
+/// The key used to sign tokens we hand out and later need to trust again.
+#[derive(Clone)]
+pub struct HmacSecret(pub Secret<String>);
+
+/// How long a confirmation link stays valid after it was sent.
+pub struct SubscriptionTokenTtl(pub Duration);
+
+fn run(
+    listener: TcpListener,
+    db_pool: PgPool,
+    email_client: EmailClient,
+    base_url: String,
+    hmac_secret: Secret<String>,
+    session_key: Key,
+    subscription_token_ttl: Duration,
+) -> Result<Server, std::io::Error> {
+    let message_store = CookieMessageStore::builder(session_key.clone()).build();
+    let message_framework = FlashMessagesFramework::builder(message_store).build();
+    let session_store = PostgresSessionStore::new(db_pool.clone());
     let connection = web::Data::new(db_pool);
+    let email_client = web::Data::new(email_client);
+    let base_url = web::Data::new(ApplicationBaseUrl(base_url));
+    let hmac_secret = web::Data::new(HmacSecret(hmac_secret));
+    let subscription_token_ttl = web::Data::new(SubscriptionTokenTtl(subscription_token_ttl));
     let server = HttpServer::new(move || {
         App::new()
+            .wrap(message_framework.clone())
+            .wrap(SessionMiddleware::new(
+                session_store.clone(),
+                session_key.clone(),
+            ))
             .wrap(TracingLogger::default())
             .route("/health_check", web::get().to(health_check))
+            .route("/readiness_check", web::get().to(readiness_check))
+            .service(
+                web::scope("/admin")
+                    .wrap(from_fn(reject_anonymous_users))
+                    .route("/dashboard", web::get().to(admin_dashboard))
+                    .route("/password", web::get().to(change_password_form))
+                    .route("/password", web::post().to(change_password))
+                    .route("/logout", web::post().to(log_out))
+                    .route("/subscribers", web::get().to(list_subscribers))
+                    .route("/subscribers/import", web::post().to(import_subscribers))
+                    .route("/subscribers/export", web::get().to(export_subscribers))
+                    .route("/dead_letters", web::get().to(list_dead_letters))
+                    .route(
+                        "/dead_letters/{newsletter_issue_id}/{subscriber_id}/requeue",
+                        web::post().to(requeue_dead_letter),
+                    ),
+            )
+            .route("/login", web::get().to(login_form))
+            .route("/login", web::post().to(login))
+            .route("/newsletters", web::post().to(publish_newsletter))
             .route("/subscriptions", web::post().to(subscribe))
+            .route("/subscriptions/confirm", web::get().to(confirm))
+            .route(
+                "/subscriptions/unsubscribe",
+                web::get().to(unsubscribe_form),
+            )
+            .route("/subscriptions/unsubscribe", web::post().to(unsubscribe))
             .app_data(connection.clone())
+            .app_data(email_client.clone())
+            .app_data(base_url.clone())
+            .app_data(hmac_secret.clone())
+            .app_data(subscription_token_ttl.clone())
     })
     .listen(listener)?
     .run();
@@ -0,0 +1,50 @@
+use uuid::Uuid;
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestUser};
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_access_the_admin_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_admin_dashboard().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn the_dashboard_greets_the_logged_in_user() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let html_page = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(html_page.contains(&format!("Welcome {}", app.test_user.username)));
+    assert!(html_page.contains(r#"href="/admin/password""#));
+    assert!(html_page.contains(r#"action="/admin/logout""#));
+}
+
+#[tokio::test]
+async fn the_username_is_html_escaped_on_the_dashboard() {
+    // Arrange
+    let app = spawn_app().await;
+    let id = Uuid::new_v4();
+    let user = TestUser {
+        username: format!("<b>{id}</b>"),
+        ..TestUser::generate()
+    };
+    user.store(&app.database_pool).await;
+    user.login(&app).await;
+
+    // Act
+    let html_page = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(!html_page.contains(&user.username));
+    assert!(html_page.contains(&format!("Welcome &lt;b&gt;{id}&lt;/b&gt;!")));
+}
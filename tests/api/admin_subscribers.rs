@@ -0,0 +1,247 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+
+async fn insert_subscriber(
+    app: &TestApp,
+    email: &str,
+    name: &str,
+    status: &str,
+    subscribed_at: DateTime<Utc>,
+) {
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status, source)
+        VALUES ($1, $2, $3, $4, $5, 'signup')
+        "#,
+        Uuid::new_v4(),
+        email,
+        name,
+        subscribed_at,
+        status
+    )
+    .execute(&app.database_pool)
+    .await
+    .expect("Failed to insert subscriber.");
+}
+
+async fn get_subscribers_page(app: &TestApp, query: &[(&str, &str)]) -> serde_json::Value {
+    app.get_admin_subscribers(query)
+        .await
+        .error_for_status()
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+fn emails(page: &serde_json::Value) -> Vec<&str> {
+    page["subscribers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["email"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_list_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_admin_subscribers(&[]).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn subscribers_are_paginated_most_recent_first() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let now = Utc::now();
+    for i in 0..5 {
+        insert_subscriber(
+            &app,
+            &format!("bunny{}@mewbun.com", i),
+            "bunny",
+            "confirmed",
+            now - Duration::try_minutes(i).unwrap(),
+        )
+        .await;
+    }
+
+    // Act - Part 1 - First page
+    let page = get_subscribers_page(&app, &[("limit", "2")]).await;
+    assert_eq!(emails(&page), ["bunny0@mewbun.com", "bunny1@mewbun.com"]);
+
+    // Act - Part 2 - Second page
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page = get_subscribers_page(&app, &[("limit", "2"), ("cursor", cursor)]).await;
+    assert_eq!(emails(&page), ["bunny2@mewbun.com", "bunny3@mewbun.com"]);
+
+    // Act - Part 3 - Last page
+    let cursor = page["next_cursor"].as_str().unwrap();
+    let page = get_subscribers_page(&app, &[("limit", "2"), ("cursor", cursor)]).await;
+    assert_eq!(emails(&page), ["bunny4@mewbun.com"]);
+    assert!(page["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn subscribers_sharing_a_timestamp_are_not_skipped_across_pages() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let now = Utc::now();
+    for i in 0..3 {
+        insert_subscriber(
+            &app,
+            &format!("twin{}@mewbun.com", i),
+            "twin",
+            "confirmed",
+            now,
+        )
+        .await;
+    }
+
+    // Act
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut query = vec![("limit", "1")];
+        if let Some(cursor) = &cursor {
+            query.push(("cursor", cursor));
+        }
+        let page = get_subscribers_page(&app, &query).await;
+        seen.extend(emails(&page).into_iter().map(String::from));
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_owned()),
+            None => break,
+        }
+    }
+
+    // Assert
+    seen.sort();
+    assert_eq!(
+        seen,
+        ["twin0@mewbun.com", "twin1@mewbun.com", "twin2@mewbun.com"]
+    );
+}
+
+#[tokio::test]
+async fn subscribers_can_be_filtered_by_status() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let now = Utc::now();
+    insert_subscriber(&app, "pending@mewbun.com", "p", "pending_confirmation", now).await;
+    insert_subscriber(&app, "confirmed@mewbun.com", "c", "confirmed", now).await;
+    insert_subscriber(&app, "gone@mewbun.com", "g", "unsubscribed", now).await;
+
+    // Act
+    let page = get_subscribers_page(&app, &[("status", "confirmed")]).await;
+
+    // Assert
+    assert_eq!(emails(&page), ["confirmed@mewbun.com"]);
+    assert_eq!(page["subscribers"][0]["status"], "confirmed");
+}
+
+#[tokio::test]
+async fn subscribers_can_be_filtered_by_date_range() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let now = Utc::now();
+    insert_subscriber(
+        &app,
+        "old@mewbun.com",
+        "o",
+        "confirmed",
+        now - Duration::try_days(10).unwrap(),
+    )
+    .await;
+    insert_subscriber(
+        &app,
+        "mid@mewbun.com",
+        "m",
+        "confirmed",
+        now - Duration::try_days(5).unwrap(),
+    )
+    .await;
+    insert_subscriber(&app, "new@mewbun.com", "n", "confirmed", now).await;
+    let after = (now - Duration::try_days(7).unwrap()).to_rfc3339();
+    let before = (now - Duration::try_days(1).unwrap()).to_rfc3339();
+
+    // Act
+    let page = get_subscribers_page(
+        &app,
+        &[("subscribed_after", &after), ("subscribed_before", &before)],
+    )
+    .await;
+
+    // Assert
+    assert_eq!(emails(&page), ["mid@mewbun.com"]);
+}
+
+#[tokio::test]
+async fn search_matches_case_insensitive_prefixes_of_email_or_name() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let now = Utc::now();
+    insert_subscriber(&app, "mews@mewbun.com", "Bunny", "confirmed", now).await;
+    insert_subscriber(&app, "other@mewbun.com", "Mewsbunny", "confirmed", now).await;
+    insert_subscriber(&app, "nope@mewbun.com", "Not a mews", "confirmed", now).await;
+
+    // Act
+    let page = get_subscribers_page(&app, &[("search", "MEWS")]).await;
+
+    // Assert
+    let mut found = emails(&page);
+    found.sort();
+    assert_eq!(found, ["mews@mewbun.com", "other@mewbun.com"]);
+}
+
+#[tokio::test]
+async fn search_wildcards_are_matched_literally() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    insert_subscriber(&app, "bunny@mewbun.com", "bunny", "confirmed", Utc::now()).await;
+
+    // Act
+    let page = get_subscribers_page(&app, &[("search", "%")]).await;
+
+    // Assert
+    assert!(emails(&page).is_empty());
+}
+
+#[tokio::test]
+async fn invalid_parameters_are_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = [
+        (vec![("limit", "0")], "a limit of 0"),
+        (vec![("limit", "101")], "a limit above the maximum"),
+        (vec![("cursor", "garbage")], "an invalid cursor"),
+        (vec![("status", "sleeping")], "an unknown status"),
+        (vec![("subscribed_after", "yesterday")], "an invalid date"),
+    ];
+
+    for (query, description) in test_cases {
+        // Act
+        let response = app.get_admin_subscribers(&query).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for {}.",
+            description
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+
+async fn import(app: &TestApp, csv: &str) {
+    app.post_subscribers_import(csv)
+        .await
+        .error_for_status()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_export_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_subscribers_export(&[]).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn subscribers_are_exported_as_csv_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    import(
+        &app,
+        "email,name,subscribed_at\n\
+        mews@mewbun.com,\"Mews, the bunny\",2021-03-04T05:06:07Z\n\
+        bunny@mewbun.com,Bunny,2022-03-04T05:06:07Z\n",
+    )
+    .await;
+
+    // Act
+    let response = app.get_subscribers_export(&[]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "text/csv; charset=utf-8"
+    );
+    let body = response.text().await.unwrap();
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(
+        lines[0],
+        "id,email,name,status,source,subscribed_at,unsubscribed_at"
+    );
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1]
+        .contains(r#",mews@mewbun.com,"Mews, the bunny",confirmed,import,2021-03-04T05:06:07Z,"#));
+    assert!(lines[2].contains(",bunny@mewbun.com,Bunny,"));
+}
+
+#[tokio::test]
+async fn subscribers_can_be_exported_as_json_lines() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    import(
+        &app,
+        "email,name\nmews@mewbun.com,Mews\nbunny@mewbun.com,Bunny\n",
+    )
+    .await;
+
+    // Act
+    let response = app.get_subscribers_export(&[("format", "ndjson")]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "application/x-ndjson");
+    let body = response.text().await.unwrap();
+    let subscribers: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(subscribers.len(), 2);
+    for subscriber in subscribers {
+        assert_eq!(subscriber["status"], "confirmed");
+        assert_eq!(subscriber["source"], "import");
+        assert!(subscriber["unsubscribed_at"].is_null());
+    }
+}
+
+#[tokio::test]
+async fn exports_can_be_filtered_by_status() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    import(
+        &app,
+        "email,name\nmews@mewbun.com,Mews\nbunny@mewbun.com,Bunny\n",
+    )
+    .await;
+    sqlx::query!(
+        r#"
+        UPDATE subscriptions
+        SET status = 'unsubscribed', unsubscribed_at = now()
+        WHERE email = 'bunny@mewbun.com'
+        "#
+    )
+    .execute(&app.database_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let response = app
+        .get_subscribers_export(&[("format", "ndjson"), ("status", "unsubscribed")])
+        .await;
+
+    // Assert
+    let body = response.text().await.unwrap();
+    let subscribers: Vec<serde_json::Value> = body
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(subscribers.len(), 1);
+    assert_eq!(subscribers[0]["email"], "bunny@mewbun.com");
+    assert!(subscribers[0]["unsubscribed_at"].is_string());
+}
+
+#[tokio::test]
+async fn every_subscriber_is_exported() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut csv = String::from("email,name\n");
+    for i in 0..3000 {
+        csv.push_str(&format!("bunny{i}@mewbun.com,bunny {i}\n"));
+    }
+    import(&app, &csv).await;
+
+    // Act
+    let response = app.get_subscribers_export(&[]).await;
+
+    // Assert
+    let body = response.text().await.unwrap();
+    // The header, then one line per subscriber.
+    assert_eq!(body.lines().count(), 3001);
+}
+
+#[tokio::test]
+async fn unknown_formats_are_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_subscribers_export(&[("format", "xml")]).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn subscribers_sharing_a_timestamp_are_not_lost_between_pages() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut csv = String::from("email,name,subscribed_at\n");
+    for i in 0..2500 {
+        csv.push_str(&format!(
+            "bunny{i}@mewbun.com,bunny {i},2021-03-04T05:06:07Z\n"
+        ));
+    }
+    import(&app, &csv).await;
+
+    // Act
+    let response = app.get_subscribers_export(&[("format", "ndjson")]).await;
+
+    // Assert
+    let body = response.text().await.unwrap();
+    let mut emails: Vec<String> = body
+        .lines()
+        .map(|line| {
+            let subscriber: serde_json::Value = serde_json::from_str(line).unwrap();
+            subscriber["email"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    emails.sort();
+    emails.dedup();
+    assert_eq!(emails.len(), 2500);
+}
@@ -0,0 +1,180 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_import_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscribers_import("email,name\nbunny@mewbun.com,bunny\n")
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn imported_subscribers_are_confirmed_and_marked_as_imported() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name,subscribed_at\n\
+        mews@mewbun.com,Mews,2021-03-04T05:06:07Z\n\
+        bunny@mewbun.com,Bunny,\n";
+
+    // Act
+    let response = app.post_subscribers_import(csv).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported"], 2);
+    assert_eq!(report["skipped_duplicates"], 0);
+    assert!(report["rejected"].as_array().unwrap().is_empty());
+
+    let saved = sqlx::query!(
+        r#"
+        SELECT email, name, status, source, subscribed_at
+        FROM subscriptions
+        ORDER BY email
+        "#
+    )
+    .fetch_all(&app.database_pool)
+    .await
+    .expect("Failed to fetch saved subscribers.");
+    assert_eq!(saved.len(), 2);
+    assert_eq!(saved[0].email, "bunny@mewbun.com");
+    assert_eq!(saved[1].email, "mews@mewbun.com");
+    assert_eq!(saved[1].name, "Mews");
+    assert_eq!(
+        saved[1].subscribed_at.to_rfc3339(),
+        "2021-03-04T05:06:07+00:00"
+    );
+    for subscriber in saved {
+        assert_eq!(subscriber.status, "confirmed");
+        assert_eq!(subscriber.source, "import");
+    }
+}
+
+#[tokio::test]
+async fn invalid_rows_are_reported_with_their_line_number() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csv = "email,name\n\
+        mews@mewbun.com,Mews\n\
+        definitely-not-an-email,Bunny\n\
+        bunny@mewbun.com,\n\
+        mewsbunny@mewbun.com,Mewsbunny\n";
+
+    // Act
+    let response = app.post_subscribers_import(csv).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported"], 2);
+    let rejected = report["rejected"].as_array().unwrap();
+    assert_eq!(rejected.len(), 2);
+    assert_eq!(rejected[0]["line"], 3);
+    assert_eq!(rejected[1]["line"], 4);
+}
+
+#[tokio::test]
+async fn duplicates_are_skipped() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_subscribers_import("email,name\nmews@mewbun.com,Mews\n")
+        .await
+        .error_for_status()
+        .unwrap();
+    sqlx::query!("UPDATE subscriptions SET status = 'unsubscribed'")
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+    let csv = "email,name\n\
+        mews@mewbun.com,Mews again\n\
+        bunny@mewbun.com,Bunny\n\
+        bunny@mewbun.com,Bunny twice\n";
+
+    // Act
+    let response = app.post_subscribers_import(csv).await;
+
+    // Assert
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["skipped_duplicates"], 2);
+    let mews =
+        sqlx::query!("SELECT name, status FROM subscriptions WHERE email = 'mews@mewbun.com'")
+            .fetch_one(&app.database_pool)
+            .await
+            .unwrap();
+    assert_eq!(mews.name, "Mews");
+    assert_eq!(mews.status, "unsubscribed");
+}
+
+#[tokio::test]
+async fn imports_larger_than_a_batch_are_fully_inserted() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let mut csv = String::from("email,name\n");
+    for i in 0..2500 {
+        csv.push_str(&format!("bunny{i}@mewbun.com,bunny {i}\n"));
+    }
+
+    // Act
+    let response = app.post_subscribers_import(&csv).await;
+
+    // Assert
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported"], 2500);
+    let n_subscribers = sqlx::query!(r#"SELECT count(*) as "count!" FROM subscriptions"#)
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_subscribers, 2500);
+}
+
+#[tokio::test]
+async fn malformed_uploads_are_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = [
+        ("file", "name\nbunny\n", "missing the email column"),
+        (
+            "file",
+            "email\nbunny@mewbun.com\n",
+            "missing the name column",
+        ),
+        (
+            "attachment",
+            "email,name\nbunny@mewbun.com,bunny\n",
+            "missing the file field",
+        ),
+    ];
+
+    for (field_name, content, description) in test_cases {
+        // Act
+        let response = app.post_subscribers_import_form(field_name, content).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the upload was {}.",
+            description
+        );
+    }
+
+    let n_subscribers = sqlx::query!(r#"SELECT count(*) as "count!" FROM subscriptions"#)
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap()
+        .count;
+    assert_eq!(n_subscribers, 0);
+}
@@ -0,0 +1,92 @@
+use claims::{assert_err, assert_ok_eq};
+use secrecy::Secret;
+use uuid::Uuid;
+use zero2prod::authentication::{create_user, validate_credentials, AuthError, Credentials};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn valid_credentials_resolve_to_the_user_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let credentials = Credentials {
+        username: app.test_user.username.clone(),
+        password: Secret::new(app.test_user.password.clone()),
+    };
+
+    // Act
+    let outcome = validate_credentials(credentials, &app.database_pool).await;
+
+    // Assert
+    assert_ok_eq!(outcome, app.test_user.user_id);
+}
+
+#[tokio::test]
+async fn a_wrong_password_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let credentials = Credentials {
+        username: app.test_user.username.clone(),
+        password: Secret::new(Uuid::new_v4().to_string()),
+    };
+
+    // Act
+    let outcome = validate_credentials(credentials, &app.database_pool).await;
+
+    // Assert
+    assert!(matches!(outcome, Err(AuthError::InvalidCredentials(_))));
+}
+
+#[tokio::test]
+async fn an_unknown_username_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let credentials = Credentials {
+        username: Uuid::new_v4().to_string(),
+        password: Secret::new(app.test_user.password.clone()),
+    };
+
+    // Act
+    let outcome = validate_credentials(credentials, &app.database_pool).await;
+
+    // Assert
+    assert_err!(&outcome);
+    assert!(matches!(outcome, Err(AuthError::InvalidCredentials(_))));
+}
+
+#[tokio::test]
+async fn created_users_can_log_in_with_their_password() {
+    // Arrange
+    let app = spawn_app().await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act
+    let user_id = create_user(&username, Secret::new(password.clone()), &app.database_pool)
+        .await
+        .unwrap();
+
+    // Assert
+    let credentials = Credentials {
+        username,
+        password: Secret::new(password),
+    };
+    let outcome = validate_credentials(credentials, &app.database_pool).await;
+    assert_ok_eq!(outcome, user_id);
+}
+
+#[tokio::test]
+async fn no_user_exists_until_one_is_created() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let users = sqlx::query!("SELECT username FROM users")
+        .fetch_all(&app.database_pool)
+        .await
+        .unwrap();
+
+    // Assert - Only the user created by the test harness
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].username, app.test_user.username);
+}
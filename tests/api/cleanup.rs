@@ -0,0 +1,134 @@
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod::cleanup_worker::{try_cleanup, CleanupOutcome};
+
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_unconfirmed_subscriber(app: &TestApp, email: &str) {
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .named("Create unconfirmed subscriber")
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let n_email_requests = app.email_server.received_requests().await.unwrap().len();
+    app.post_subscriptions(format!("name=bunny&email={}", email))
+        .await
+        .error_for_status()
+        .unwrap();
+    app.wait_for_email_requests(n_email_requests + 1).await;
+}
+
+#[tokio::test]
+async fn pending_subscribers_with_expired_tokens_are_purged() {
+    // Arrange
+    let app = spawn_app().await;
+    create_unconfirmed_subscriber(&app, "stale%40mewbun.com").await;
+    create_unconfirmed_subscriber(&app, "fresh%40mewbun.com").await;
+    sqlx::query(
+        r#"
+        UPDATE subscription_tokens
+        SET created_at = now() - interval '8 days'
+        FROM subscriptions
+        WHERE subscriptions.id = subscriber_id AND email = 'stale@mewbun.com'
+        "#,
+    )
+    .execute(&app.database_pool)
+    .await
+    .unwrap();
+
+    // Act
+    let outcome = try_cleanup(&app.database_pool, &app.cleanup).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome.expired_subscribers, 1);
+    let remaining = sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_all(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].email, "fresh@mewbun.com");
+}
+
+#[tokio::test]
+async fn confirmed_subscribers_are_never_purged() {
+    // Arrange
+    let app = spawn_app().await;
+    create_unconfirmed_subscriber(&app, "mewsbunny%40mewbun.com").await;
+    sqlx::query("UPDATE subscriptions SET status = 'confirmed'")
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE subscription_tokens SET created_at = now() - interval '30 days'")
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let outcome = try_cleanup(&app.database_pool, &app.cleanup).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome, CleanupOutcome::default());
+}
+
+#[tokio::test]
+async fn idempotency_keys_older_than_their_ttl_are_deleted() {
+    // Arrange
+    let app = spawn_app().await;
+    for (key, age) in [("old", "3 days"), ("recent", "1 hour")] {
+        sqlx::query(
+            r#"
+            INSERT INTO idempotency (user_id, idempotency_key, created_at)
+            VALUES ($1, $2, now() - $3::interval)
+            "#,
+        )
+        .bind(app.test_user.user_id)
+        .bind(format!("{}-{}", key, Uuid::new_v4()))
+        .bind(age)
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+    }
+
+    // Act
+    let outcome = try_cleanup(&app.database_pool, &app.cleanup).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome.idempotency_keys, 1);
+    let remaining = sqlx::query!("SELECT idempotency_key FROM idempotency")
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap();
+    assert!(remaining.idempotency_key.starts_with("recent"));
+}
+
+#[tokio::test]
+async fn expired_sessions_are_deleted() {
+    // Arrange
+    let app = spawn_app().await;
+    for (key, expires_in) in [("expired", "-1 hour"), ("live", "1 hour")] {
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (session_key, state, expires_at)
+            VALUES ($1, '{}'::jsonb, now() + $2::interval)
+            "#,
+        )
+        .bind(key)
+        .bind(expires_in)
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+    }
+
+    // Act
+    let outcome = try_cleanup(&app.database_pool, &app.cleanup).await.unwrap();
+
+    // Assert
+    assert_eq!(outcome.expired_sessions, 1);
+    let remaining = sqlx::query!("SELECT session_key FROM sessions")
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.session_key, "live");
+}
@@ -0,0 +1,15 @@
+mod admin_dashboard;
+mod admin_subscribers;
+mod admin_subscribers_export;
+mod admin_subscribers_import;
+mod authentication;
+mod change_password;
+mod cleanup;
+mod health_check;
+mod helpers;
+mod login;
+mod logout;
+mod newsletters;
+mod subscriptions;
+mod subscriptions_confirm;
+mod subscriptions_unsubscribe;
@@ -0,0 +1,661 @@
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use uuid::Uuid;
+
+use crate::helpers::{assert_is_redirect_to, spawn_app, ConfirmationLinks, TestApp, TestUser};
+
+/// Use the public API of the application under test to create
+/// an unconfirmed subscriber.
+async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
+    // Every call registers a different subscriber.
+    let body = format!(
+        "name=bunny%20mcbunbun&email={}%40mewbun.com",
+        Uuid::new_v4()
+    );
+
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .named("Create unconfirmed subscriber")
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let n_email_requests = app.email_server.received_requests().await.unwrap().len();
+    app.post_subscriptions(body)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    let email_request = &app
+        .wait_for_email_requests(n_email_requests + 1)
+        .await
+        .pop()
+        .unwrap();
+    app.get_confirmation_links(email_request)
+}
+
+async fn create_confirmed_subscriber(app: &TestApp) {
+    let confirmation_link = create_unconfirmed_subscriber(app).await;
+    reqwest::get(confirmation_link.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
+fn newsletter_request_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "content": {
+            "text": "Newsletter body as plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+        }
+    })
+}
+
+#[tokio::test]
+async fn newsletters_are_not_delivered_to_unconfirmed_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    create_unconfirmed_subscriber(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        // We assert that no request is fired at Postmark!
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn newsletters_are_delivered_to_confirmed_subscribers() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    // Every issue must let its reader leave the list in one click.
+    app.get_unsubscribe_link(&email_request);
+}
+
+#[tokio::test]
+async fn subscribers_with_invalid_stored_emails_are_skipped() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    sqlx::query(
+        r#"
+        INSERT INTO subscriptions (id, email, name, subscribed_at, status, source)
+        VALUES ($1, 'definitely-not-an-email', 'legacy', now(), 'confirmed', 'signup')
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .execute(&app.database_pool)
+    .await
+    .unwrap();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn newsletters_returns_400_for_invalid_data() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            serde_json::json!({
+                "content": {
+                    "text": "Newsletter body as plain text",
+                    "html": "<p>Newsletter body as HTML</p>",
+                }
+            }),
+            "missing title",
+        ),
+        (
+            serde_json::json!({"title": "Newsletter!"}),
+            "missing content",
+        ),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        // Act
+        let response = app.post_newsletters(invalid_body).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request when the payload was {}.",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn publishing_only_enqueues_deliveries() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    let queued = sqlx::query!("SELECT subscriber_id FROM issue_delivery_queue")
+        .fetch_all(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(queued.len(), 1);
+}
+
+#[tokio::test]
+async fn concurrent_workers_deliver_each_email_exactly_once() {
+    // Arrange
+    let app = spawn_app().await;
+    for _ in 0..5 {
+        create_confirmed_subscriber(&app).await;
+    }
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(5)
+        .mount(&app.email_server)
+        .await;
+    app.post_newsletters(newsletter_request_body())
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Act
+    tokio::join!(
+        app.dispatch_all_pending_emails(),
+        app.dispatch_all_pending_emails(),
+        app.dispatch_all_pending_emails(),
+    );
+
+    // Assert: mock expectations are checked on drop
+}
+
+async fn publish_issue_with_failing_email_api(app: &TestApp, status: u16) {
+    create_confirmed_subscriber(app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(&app.email_server)
+        .await;
+    app.post_newsletters(newsletter_request_body())
+        .await
+        .error_for_status()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn transient_failures_are_rescheduled_with_backoff() {
+    // Arrange
+    let app = spawn_app().await;
+    publish_issue_with_failing_email_api(&app, 500).await;
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let task = sqlx::query!(
+        "SELECT n_retries, execute_after > now() AS \"in_the_future!\" FROM issue_delivery_queue"
+    )
+    .fetch_one(&app.database_pool)
+    .await
+    .unwrap();
+    assert_eq!(task.n_retries, 1);
+    assert!(task.in_the_future);
+}
+
+#[tokio::test]
+async fn deliveries_are_dead_lettered_once_attempts_are_exhausted() {
+    // Arrange
+    let app = spawn_app().await;
+    publish_issue_with_failing_email_api(&app, 500).await;
+    sqlx::query("UPDATE issue_delivery_queue SET n_retries = $1")
+        .bind(i16::try_from(app.issue_delivery.max_attempts - 1).unwrap())
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let queued = sqlx::query!("SELECT subscriber_id FROM issue_delivery_queue")
+        .fetch_all(&app.database_pool)
+        .await
+        .unwrap();
+    assert!(queued.is_empty());
+    let dead_letter = sqlx::query!("SELECT n_attempts FROM issue_delivery_dead_letters")
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        dead_letter.n_attempts as u16,
+        app.issue_delivery.max_attempts
+    );
+}
+
+#[tokio::test]
+async fn permanent_failures_are_dead_lettered_immediately() {
+    // Arrange
+    let app = spawn_app().await;
+    publish_issue_with_failing_email_api(&app, 422).await;
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let dead_letter = sqlx::query!("SELECT n_attempts FROM issue_delivery_dead_letters")
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(dead_letter.n_attempts, 1);
+}
+
+#[tokio::test]
+async fn dead_letters_can_be_listed_and_requeued() {
+    // Arrange
+    let app = spawn_app().await;
+    publish_issue_with_failing_email_api(&app, 422).await;
+    app.dispatch_all_pending_emails().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - List dead letters
+    let dead_letters: serde_json::Value = app
+        .api_client
+        .get(format!("{}/admin/dead_letters", app.address))
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let dead_letters = dead_letters.as_array().unwrap();
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0]["title"], "Newsletter title");
+
+    // Act - Part 2 - Requeue it
+    let response = app
+        .api_client
+        .post(format!(
+            "{}/admin/dead_letters/{}/{}/requeue",
+            app.address,
+            dead_letters[0]["newsletter_issue_id"].as_str().unwrap(),
+            dead_letters[0]["subscriber_id"].as_str().unwrap(),
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    let task = sqlx::query!("SELECT n_retries FROM issue_delivery_queue")
+        .fetch_one(&app.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(task.n_retries, 0);
+    let dead_letters = sqlx::query!("SELECT n_attempts FROM issue_delivery_dead_letters")
+        .fetch_all(&app.database_pool)
+        .await
+        .unwrap();
+    assert!(dead_letters.is_empty());
+}
+
+#[tokio::test]
+async fn requeueing_an_unknown_dead_letter_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!(
+            "{}/admin/dead_letters/{}/{}/requeue",
+            app.address,
+            Uuid::new_v4(),
+            Uuid::new_v4()
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn newsletter_creation_is_idempotent() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Publish the newsletter
+    let response = app
+        .post_newsletters_with_idempotency_key(newsletter_request_body(), &idempotency_key)
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    // Act - Part 2 - Publish it again
+    let response = app
+        .post_newsletters_with_idempotency_key(newsletter_request_body(), &idempotency_key)
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that we have sent the newsletter email **once**
+}
+
+#[tokio::test]
+async fn concurrent_publish_requests_are_handled_gracefully() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Submit two publish requests concurrently
+    let (response1, response2) = tokio::join!(
+        app.post_newsletters_with_idempotency_key(newsletter_request_body(), &idempotency_key),
+        app.post_newsletters_with_idempotency_key(newsletter_request_body(), &idempotency_key),
+    );
+
+    // Assert
+    assert_eq!(response1.status(), response2.status());
+    assert_eq!(
+        response1.text().await.unwrap(),
+        response2.text().await.unwrap()
+    );
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that we have sent the newsletter email **once**
+}
+
+#[tokio::test]
+async fn publishing_with_an_invalid_idempotency_key_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+    let too_long = "a".repeat(50);
+    let test_cases = vec![("", "empty key"), (too_long.as_str(), "key too long")];
+
+    for (idempotency_key, description) in test_cases {
+        // Act
+        let response = app
+            .post_newsletters_with_idempotency_key(newsletter_request_body(), idempotency_key)
+            .await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not fail with 400 Bad Request for an {}.",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn requests_missing_authorization_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .json(&newsletter_request_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Basic realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn requests_missing_authorization_are_rejected_before_the_body_is_parsed() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .header("Content-Type", "application/json")
+        .body("this is not a newsletter")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Basic realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn non_basic_authorization_schemes_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .bearer_auth(Uuid::new_v4().to_string())
+        .json(&newsletter_request_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Basic realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn non_existing_user_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .basic_auth(username, Some(password))
+        .json(&newsletter_request_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Basic realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn invalid_password_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let username = &app.test_user.username;
+    let password = Uuid::new_v4().to_string();
+    assert_ne!(app.test_user.password, password);
+
+    // Act
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .basic_auth(username, Some(password))
+        .json(&newsletter_request_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        r#"Basic realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn idempotency_keys_are_scoped_to_the_publishing_user() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    let other_user = TestUser::generate();
+    other_user.store(&app.database_pool).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Both users publish with the same key
+    let response = app
+        .post_newsletters_with_idempotency_key(newsletter_request_body(), &idempotency_key)
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    let response = reqwest::Client::new()
+        .post(format!("{}/newsletters", &app.address))
+        .basic_auth(&other_user.username, Some(&other_user.password))
+        .header("Idempotency-Key", &idempotency_key)
+        .json(&newsletter_request_body())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 202);
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that each user's issue was delivered
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_dead_letters() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/admin/dead_letters", app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_requeue_dead_letters() {
+    // Arrange
+    let app = spawn_app().await;
+    publish_issue_with_failing_email_api(&app, 422).await;
+    app.dispatch_all_pending_emails().await;
+    let dead_letter =
+        sqlx::query!("SELECT newsletter_issue_id, subscriber_id FROM issue_delivery_dead_letters")
+            .fetch_one(&app.database_pool)
+            .await
+            .unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!(
+            "{}/admin/dead_letters/{}/{}/requeue",
+            app.address, dead_letter.newsletter_issue_id, dead_letter.subscriber_id
+        ))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let n_dead_letters =
+        sqlx::query!(r#"SELECT count(*) as "count!" FROM issue_delivery_dead_letters"#)
+            .fetch_one(&app.database_pool)
+            .await
+            .unwrap()
+            .count;
+    assert_eq!(n_dead_letters, 1);
+}
@@ -0,0 +1,319 @@
+use std::time::{Duration, Instant};
+
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn subscribe_returns_200_when_valid_data_present() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn subscribe_persists_the_new_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions(body.into()).await;
+
+    // Assert
+    let saved = sqlx::query!("SELECT name, email, status FROM subscriptions")
+        .fetch_one(&app.database_pool)
+        .await
+        .expect("Failed to fetch saved subscription");
+
+    assert_eq!(saved.email, "mewsbunny@mewbun.com");
+    assert_eq!(saved.name, "bunny mcbunbun");
+    assert_eq!(saved.status, "pending_confirmation");
+}
+
+#[tokio::test]
+async fn subscribe_returns_400_when_data_is_missing() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        ("name=bunny%20mcbunbun", "missing email"),
+        ("email=mewsbunny%40mewbun.com", "missing name"),
+        ("", "missing both name and email"),
+    ];
+
+    for (invalid_body, error_message) in test_cases {
+        // Act
+        let response = app.post_subscriptions(invalid_body.into()).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "API did not return 400 when payload was {}",
+            error_message
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribe_returns_400_when_fields_are_present_but_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        ("name=&email=mewsbunny%40mewbun.com", "empty name"),
+        ("name=bunny&email=", "empty email"),
+        ("name=bunny&email=definitely-not-an-email", "invalid email"),
+        (
+            "name=%7Bbunny%7D&email=mewsbunny%40mewbun.com",
+            "forbidden characters in name",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        // Act
+        let response = app.post_subscriptions(body.into()).await;
+
+        // Assert
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "API did not return 400 when the payload was {}",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn subscribe_sends_a_confirmation_email_with_a_link() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions(body.into()).await;
+
+    // Assert
+    let email_request = &app.wait_for_email_requests(1).await[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+
+    // The two links should be identical
+    assert_eq!(confirmation_links.html, confirmation_links.plain_text);
+}
+
+#[tokio::test]
+async fn subscribe_fails_if_there_is_a_fatal_database_error() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+    // Sabotage the database
+    sqlx::query("ALTER TABLE subscription_tokens DROP COLUMN subscription_token;")
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 500);
+    assert_eq!(response.text().await.unwrap(), "");
+}
+
+#[tokio::test]
+async fn subscribe_explains_why_the_data_is_invalid() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny&email=definitely-not-an-email";
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("definitely-not-an-email"));
+}
+
+#[tokio::test]
+async fn subscribe_returns_200_even_if_the_confirmation_email_cannot_be_sent() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    app.wait_for_email_requests(1).await;
+}
+
+#[tokio::test]
+async fn subscribe_does_not_wait_for_the_confirmation_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let start = Instant::now();
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    // A confirmed address gets its answer without any email being sent: a
+    // new sign-up must not take noticeably longer.
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn subscribe_does_not_persist_the_subscriber_if_the_token_cannot_be_stored() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+    sqlx::query("ALTER TABLE subscription_tokens DROP COLUMN subscription_token;")
+        .execute(&app.database_pool)
+        .await
+        .unwrap();
+
+    // Act
+    app.post_subscriptions(body.into()).await;
+
+    // Assert
+    let saved = sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_all(&app.database_pool)
+        .await
+        .expect("Failed to fetch subscriptions.");
+    assert!(saved.is_empty());
+}
+
+#[tokio::test]
+async fn subscribing_twice_while_pending_resends_the_confirmation_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let first_response = app.post_subscriptions(body.into()).await;
+    let second_response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(first_response.status().as_u16(), 200);
+    assert_eq!(second_response.status().as_u16(), 200);
+    assert_eq!(app.wait_for_email_requests(2).await.len(), 2);
+
+    let saved = sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_all(&app.database_pool)
+        .await
+        .expect("Failed to fetch subscriptions.");
+    assert_eq!(saved.len(), 1);
+}
+
+#[tokio::test]
+async fn resubscribing_rotates_the_confirmation_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(body.into()).await;
+    app.wait_for_email_requests(1).await;
+    app.post_subscriptions(body.into()).await;
+    let email_requests = app.wait_for_email_requests(2).await;
+    let first_link = app.get_confirmation_links(&email_requests[0]).html;
+    let second_link = app.get_confirmation_links(&email_requests[1]).html;
+
+    // Act
+    let first_response = reqwest::get(first_link).await.unwrap();
+    let second_response = reqwest::get(second_link).await.unwrap();
+
+    // Assert
+    assert_eq!(first_response.status().as_u16(), 401);
+    assert_eq!(second_response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn subscribing_with_a_confirmed_email_returns_200_without_sending_an_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
+
+    let mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    let email_request = &app.wait_for_email_requests(1).await[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+    reqwest::get(confirmation_links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    drop(mock_guard);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
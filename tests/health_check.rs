@@ -1,152 +0,0 @@
-use std::net::TcpListener;
-
-use once_cell::sync::Lazy;
-use secrecy::ExposeSecret;
-use sqlx::{Connection, Executor, PgConnection, PgPool};
-use uuid::Uuid;
-use zero2prod::configuration::{get_configuration, DatabaseSettings};
-use zero2prod::startup::run;
-use zero2prod::telemetry::{get_subscriber, init_subscriber};
-
-static TRACING: Lazy<()> = Lazy::new(|| {
-    let default_filter_level = "info".to_string();
-    let subscriber_name = "test".to_string();
-
-    if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
-        init_subscriber(subscriber);
-    } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
-        init_subscriber(subscriber);
-    }
-});
-
-#[tokio::test]
-async fn health_check_works() {
-    // Arrange
-    let app = spawn_app().await;
-    let client = reqwest::Client::new();
-
-    // Act
-    let response = client
-        .get(format!("{}/health_check", app.address))
-        .send()
-        .await
-        .expect("Failed to execute request.");
-
-    // Assert
-    assert!(response.status().is_success());
-    assert_eq!(Some(0), response.content_length());
-}
-
-#[tokio::test]
-async fn subscribe_returns_200_when_valid_data_present() {
-    // Arrange
-    let app = spawn_app().await;
-    let client = reqwest::Client::new();
-
-    // Act
-    let body = "name=bunny%20mcbunbun&email=mewsbunny%40mewbun.com";
-    let response = client
-        .post(&format!("{}/subscriptions", &app.address))
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(body)
-        .send()
-        .await
-        .expect("Failed to send POST request");
-
-    // Assert
-    assert_eq!(response.status().as_u16(), 200);
-
-    let saved = sqlx::query!("SELECT name, email FROM subscriptions")
-        .fetch_one(&app.database_pool)
-        .await
-        .expect("Failed to fetch saved subscription");
-
-    assert_eq!(saved.email, "mewsbunny@mewbun.com");
-    assert_eq!(saved.name, "bunny mcbunbun");
-}
-
-#[tokio::test]
-async fn subscribe_returns_400_when_data_is_missing() {
-    // Arrange
-    let app = spawn_app().await;
-    let client = reqwest::Client::new();
-    let test_cases = vec![
-        ("name=bunny%20mcbunbun", "missing email"),
-        ("email=mewsbunny%40mewbun.com", "missing email"),
-        ("", "missing email"),
-    ];
-
-    for (invalid_body, error_message) in test_cases {
-        // Act
-        let response = client
-            .post(&format!("{}/subscriptions", app.address))
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(invalid_body)
-            .send()
-            .await
-            .expect("Failed to send POST request");
-
-        // Assert
-        assert_eq!(
-            400,
-            response.status().as_u16(),
-            "API did not return 400 when payload was {}",
-            error_message
-        );
-    }
-}
-
-pub struct TestApp {
-    pub database_pool: PgPool,
-    pub address: String,
-}
-
-pub async fn configure_database(config: &DatabaseSettings) -> PgPool {
-    let mut connection =
-        PgConnection::connect(&config.connection_string_without_db().expose_secret())
-            .await
-            .expect("Failed to connect to Postgres");
-
-    connection
-        .execute(format!(r#"CREATE DATABASE "{}";"#, config.database_name).as_str())
-        .await
-        .expect("Failed to create database.");
-
-    let connection_pool = PgPool::connect(&config.connection_string().expose_secret())
-        .await
-        .expect("Failed to connect to Postgres");
-
-    sqlx::migrate!("./migrations")
-        .run(&connection_pool)
-        .await
-        .expect("Failed to run migrations");
-
-    connection_pool
-}
-
-async fn spawn_app() -> TestApp {
-    // Initialise logging the first time. All other times skip this step.
-    Lazy::force(&TRACING);
-
-    let listener =
-        TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener to random port.");
-
-    let port = listener.local_addr().unwrap().port();
-
-    let mut configuration = get_configuration().expect("Failed to read configuration.");
-    // Get random database name every test run
-    configuration.database.database_name = Uuid::new_v4().to_string();
-
-    let connection_pool = configure_database(&configuration.database).await;
-
-    let address = format!("http://127.0.0.1:{port}");
-    let server = run(listener, connection_pool.clone()).expect("Failed to establish server");
-    let _ = tokio::spawn(server);
-
-    TestApp {
-        address,
-        database_pool: connection_pool,
-    }
-}